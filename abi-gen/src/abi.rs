@@ -0,0 +1,240 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Conversion of `#[eth_abi]` traits to Solidity ABI JSON.
+//!
+//! This follows the conventions of `pwasm-abi-derive`: a method marked
+//! `#[event]` is an event, whose parameters are indexed if their names start
+//! with `indexed_`; a method marked `#[constant]` is a view; and a method
+//! named `constructor` is the constructor.
+//!
+//! Read https://solidity.readthedocs.io/en/develop/abi-spec.html#json for the
+//! format.
+use quote::ToTokens;
+use serde_json::{json, Value};
+use syn::{
+    ArgCaptured, Attribute, Expr, FnArg, GenericArgument, Item, ItemTrait, Lit, Meta, NestedMeta,
+    Pat, PathArguments, ReturnType, TraitItem, TraitItemMethod, Type,
+};
+
+/// The prefix that marks a parameter of an event as indexed.
+const INDEXED_PREFIX: &str = "indexed_";
+
+/// An `#[eth_abi]` trait.
+pub struct Endpoint {
+    /// The name of the generated endpoint, which is the first argument of
+    /// `#[eth_abi]`.
+    pub name: String,
+    /// The ABI entries of the methods of the trait, in order.
+    pub entries: Vec<Value>,
+}
+
+/// Appends the `#[eth_abi]` traits in `items`, and in any modules declared
+/// inline in them, to `endpoints`.
+pub fn find_endpoints(items: &[Item], endpoints: &mut Vec<Endpoint>) -> Result<(), String> {
+    for item in items {
+        match item {
+            Item::Trait(item) => {
+                if let Some(name) = endpoint_name(&item.attrs)? {
+                    endpoints.push(Endpoint {
+                        entries: entries(item)?,
+                        name,
+                    });
+                }
+            }
+            Item::Mod(item) => {
+                if let Some((_, items)) = &item.content {
+                    find_endpoints(items, endpoints)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of the endpoint if `attrs` contains `#[eth_abi]`.
+fn endpoint_name(attrs: &[Attribute]) -> Result<Option<String>, String> {
+    let attr = match attrs.iter().find(|attr| attr.path.is_ident("eth_abi")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => match list.nested.first().map(|pair| pair.into_value()) {
+            Some(NestedMeta::Meta(Meta::Word(name))) => Ok(Some(name.to_string())),
+            _ => Err("#[eth_abi] must name an endpoint".to_string()),
+        },
+        _ => Err("#[eth_abi] must name an endpoint".to_string()),
+    }
+}
+
+fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path.is_ident(name))
+}
+
+fn entries(item: &ItemTrait) -> Result<Vec<Value>, String> {
+    item.items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Method(method) => Some(entry(method)),
+            _ => None,
+        })
+        .map(|entry| entry.map_err(|e| format!("{}::{}", item.ident, e)))
+        .collect()
+}
+
+fn entry(method: &TraitItemMethod) -> Result<Value, String> {
+    let name = method.sig.ident.to_string();
+    let in_method = |e: String| format!("{}: {}", name, e);
+    let mut inputs = Vec::new();
+    for arg in &method.sig.decl.inputs {
+        if let FnArg::Captured(arg) = arg {
+            inputs.push(parameter(arg).map_err(in_method)?);
+        }
+    }
+    if has_attr(&method.attrs, "event") {
+        for input in &mut inputs {
+            let name = input["name"].as_str().unwrap_or_default().to_string();
+            let stripped = name.strip_prefix(INDEXED_PREFIX);
+            input["indexed"] = json!(stripped.is_some());
+            if let Some(stripped) = stripped {
+                input["name"] = json!(stripped);
+            }
+        }
+        return Ok(json!({
+            "type": "event",
+            "name": name,
+            "inputs": inputs,
+            "anonymous": false,
+        }));
+    }
+    if name == "constructor" {
+        return Ok(json!({
+            "type": "constructor",
+            "inputs": inputs,
+            "payable": false,
+            "stateMutability": "nonpayable",
+        }));
+    }
+    let outputs = match &method.sig.decl.output {
+        ReturnType::Default => Vec::new(),
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Tuple(tuple) => tuple.elems.iter().collect(),
+            ty => vec![ty],
+        },
+    };
+    let outputs = outputs
+        .into_iter()
+        .map(|ty| Ok(json!({ "name": "", "type": solidity_type(ty)? })))
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(in_method)?;
+    let constant = has_attr(&method.attrs, "constant");
+    Ok(json!({
+        "type": "function",
+        "name": name,
+        "inputs": inputs,
+        "outputs": outputs,
+        "constant": constant,
+        "payable": false,
+        "stateMutability": if constant { "view" } else { "nonpayable" },
+    }))
+}
+
+fn parameter(arg: &ArgCaptured) -> Result<Value, String> {
+    let name = match &arg.pat {
+        Pat::Ident(pat) => pat.ident.to_string(),
+        _ => String::new(),
+    };
+    Ok(json!({ "name": name, "type": solidity_type(&arg.ty)? }))
+}
+
+/// Returns the Solidity type that `pwasm-abi` encodes `ty` as.
+fn solidity_type(ty: &Type) -> Result<String, String> {
+    let unsupported = || Err(format!("unsupported type `{}`", ty.into_token_stream()));
+    match ty {
+        Type::Path(path) => {
+            let segment = match path.path.segments.last() {
+                Some(segment) => segment.into_value(),
+                None => return unsupported(),
+            };
+            let name = segment.ident.to_string();
+            match name.as_str() {
+                "Address" => Ok("address".to_string()),
+                "U256" => Ok("uint256".to_string()),
+                "H256" => Ok("bytes32".to_string()),
+                "bool" => Ok("bool".to_string()),
+                "String" => Ok("string".to_string()),
+                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128" => {
+                    let (sign, bits) = name.split_at(1);
+                    Ok(format!(
+                        "{}{}",
+                        if sign == "u" { "uint" } else { "int" },
+                        bits
+                    ))
+                }
+                "Vec" => match &segment.arguments {
+                    PathArguments::AngleBracketed(args) => match args.args.first() {
+                        Some(pair) => match pair.into_value() {
+                            GenericArgument::Type(element) => match solidity_type(element)? {
+                                ref element if element == "uint8" => Ok("bytes".to_string()),
+                                element => Ok(format!("{}[]", element)),
+                            },
+                            _ => unsupported(),
+                        },
+                        None => unsupported(),
+                    },
+                    _ => unsupported(),
+                },
+                _ => unsupported(),
+            }
+        }
+        Type::Array(array) => {
+            let length = match &array.len {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Int(length) => length.value(),
+                    _ => return unsupported(),
+                },
+                _ => return unsupported(),
+            };
+            match solidity_type(&array.elem)? {
+                ref element if element == "uint8" => Ok(format!("bytes{}", length)),
+                element => Ok(format!("{}[{}]", element, length)),
+            }
+        }
+        _ => unsupported(),
+    }
+}
+
+/// Returns the canonical signature of `entry`, e.g.
+/// `"function upgradeTo(uint256,address)"`.  Entries with the same
+/// signature are the same method or event.
+pub fn signature(entry: &Value) -> String {
+    let types: Vec<&str> = entry["inputs"]
+        .as_array()
+        .map(|inputs| {
+            inputs
+                .iter()
+                .map(|input| input["type"].as_str().unwrap_or_default())
+                .collect()
+        })
+        .unwrap_or_default();
+    format!(
+        "{} {}({})",
+        entry["type"].as_str().unwrap_or_default(),
+        entry["name"].as_str().unwrap_or_default(),
+        types.join(",")
+    )
+}
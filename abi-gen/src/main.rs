@@ -0,0 +1,446 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Writes the Solidity ABI of each contract as JSON, so that web3 front ends
+//! and the oracle can talk to the wasm contracts.
+//!
+//! The ABIs are read from the `#[eth_abi]` traits in the source of the
+//! contracts.  A contract's ABI is the union of the endpoints its `call`
+//! dispatches to, which are listed in [`CONTRACTS`].
+//!
+//! Usage: `cargo run -p woa-bridge-abi [OUTPUT_DIR]`.  Each ABI is written to
+//! `OUTPUT_DIR/<contract>.json`; `OUTPUT_DIR` defaults to `target/abi`.
+#![deny(warnings)]
+mod abi;
+
+use self::abi::{find_endpoints, signature, Endpoint};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// A contract, as deployed.
+struct Contract {
+    name: &'static str,
+    /// The endpoints that `call` dispatches to.  If several endpoints have a
+    /// method with the same signature, the first one is the one called.
+    endpoints: &'static [&'static str],
+    /// Whether a call with no input goes to a payable fallback.
+    payable_fallback: bool,
+}
+
+/// The endpoints of `basic_home_bridge::dispatch`.
+macro_rules! basic_home_bridge {
+    ($($endpoint:expr),*) => {
+        &[
+            "BasicHomeBridge",
+            "BridgeLimits",
+            "FeeManager",
+            "Pausable",
+            "AccessControl",
+            "Ownable",
+            $($endpoint),*
+        ]
+    };
+}
+
+/// The endpoints of `basic_foreign_bridge::dispatch`.
+macro_rules! basic_foreign_bridge {
+    ($($endpoint:expr),*) => {
+        &[
+            "BasicForeignBridge",
+            "Pausable",
+            "AccessControl",
+            "Ownable",
+            $($endpoint),*
+        ]
+    };
+}
+
+/// Every contract, with its endpoints in the order `call` tries them.  This
+/// must be kept in sync with the contracts' `call` functions.
+const CONTRACTS: &[Contract] = &[
+    Contract {
+        name: "BridgeValidators",
+        endpoints: &["AccessControl", "BridgeValidators"],
+        payable_fallback: false,
+    },
+    Contract {
+        name: "OwnedUpgradeabilityProxy",
+        endpoints: &[
+            "OwnedUpgradeabilityProxy",
+            "UpgradeabilityStorage",
+            "AccessControl",
+        ],
+        // Any other call is delegated to the implementation, value included.
+        payable_fallback: true,
+    },
+    Contract {
+        name: "ERC677BridgeToken",
+        endpoints: &["ERC20", "ERC677", "Ownable", "BurnableMintableERC677Token"],
+        payable_fallback: false,
+    },
+    Contract {
+        name: "HomeBridgeNativeToErc",
+        endpoints: basic_home_bridge!["HomeBridgeNativeToErc"],
+        payable_fallback: true,
+    },
+    Contract {
+        name: "ForeignBridgeNativeToErc",
+        endpoints: basic_foreign_bridge!["ERC677Receiver", "ForeignBridgeNativeToErc"],
+        payable_fallback: false,
+    },
+    Contract {
+        name: "HomeBridgeErcToErc",
+        endpoints: basic_home_bridge!["ERC677Receiver", "HomeBridgeErcToErc"],
+        payable_fallback: false,
+    },
+    Contract {
+        name: "ForeignBridgeErcToErc",
+        endpoints: basic_foreign_bridge!["ForeignBridgeErcToErc"],
+        payable_fallback: false,
+    },
+    Contract {
+        name: "HomeBridgeErcToNative",
+        endpoints: basic_home_bridge!["HomeBridgeErcToNative"],
+        payable_fallback: true,
+    },
+    Contract {
+        name: "MultiSigWallet",
+        endpoints: &["MultiSigWallet"],
+        payable_fallback: true,
+    },
+];
+
+/// Parses every source file under `dir`, appending them to `files`.
+fn read_sources(dir: &Path, files: &mut Vec<syn::File>) -> Result<(), String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect()
+        })
+        .map_err(|e| format!("{}: {}", dir.display(), e))?;
+    paths.sort();
+    for path in &paths {
+        if path.is_dir() {
+            read_sources(path, files)?;
+        } else if path.extension() == Some("rs".as_ref()) {
+            let source =
+                fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            files.push(syn::parse_file(&source).map_err(|e| format!("{}: {}", path.display(), e))?);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the endpoints found in the source of the contracts.
+fn read_endpoints() -> Result<Vec<Endpoint>, String> {
+    let mut files = Vec::new();
+    read_sources(&contracts_dir(), &mut files)?;
+    let mut endpoints = Vec::new();
+    for file in &files {
+        find_endpoints(&file.items, &mut endpoints)?;
+    }
+    Ok(endpoints)
+}
+
+/// The source directory of the contracts.
+fn contracts_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../test3/src")
+}
+
+/// Builds the ABI of `contract` from its endpoints.
+fn contract_abi(contract: &Contract, endpoints: &[Endpoint]) -> Result<Value, String> {
+    let mut abi = Vec::new();
+    let mut seen = BTreeSet::new();
+    for &name in contract.endpoints {
+        let endpoint = endpoints
+            .iter()
+            .find(|endpoint| endpoint.name == name)
+            .ok_or_else(|| format!("{}: no endpoint named {}", contract.name, name))?;
+        for entry in &endpoint.entries {
+            // Only the first constructor and the first method with a given
+            // signature are reachable.
+            let key = if entry["type"] == "constructor" {
+                "constructor".to_string()
+            } else {
+                signature(entry)
+            };
+            if seen.insert(key) {
+                abi.push(entry.clone());
+            }
+        }
+    }
+    if contract.payable_fallback {
+        abi.push(json!({
+            "type": "fallback",
+            "payable": true,
+            "stateMutability": "payable",
+        }));
+    }
+    Ok(Value::Array(abi))
+}
+
+fn run() -> Result<(), String> {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output_dir = env::args()
+        .nth(1)
+        .map(Into::into)
+        .unwrap_or_else(|| manifest_dir.join("../target/abi"));
+    let endpoints = read_endpoints()?;
+    fs::create_dir_all(&output_dir).map_err(|e| format!("{}: {}", output_dir.display(), e))?;
+    for contract in CONTRACTS {
+        let abi = contract_abi(contract, &endpoints)?;
+        let path = output_dir.join(format!("{}.json", contract.name));
+        let json = serde_json::to_string_pretty(&abi).expect("a JSON value is always serializable");
+        fs::write(&path, json + "\n").map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{Expr, Item, Lit};
+
+    /// The method lists that the contracts dispatch on, with the endpoint
+    /// each one is the selector table of.  The last endpoint of a contract
+    /// needs no list, as `call` falls through to it.
+    const METHOD_LISTS: &[(&str, &str)] = &[
+        ("ACCESS_CONTROL_METHODS", "AccessControl"),
+        ("PROXY_METHODS", "OwnedUpgradeabilityProxy"),
+        ("STORAGE_METHODS", "UpgradeabilityStorage"),
+        ("ERC20_METHODS", "ERC20"),
+        ("ERC677_METHODS", "ERC677"),
+        ("ERC677_RECEIVER_METHODS", "ERC677Receiver"),
+        ("TOKEN_METHODS", "BurnableMintableERC677Token"),
+        ("OWNABLE_METHODS", "Ownable"),
+        ("BASIC_HOME_BRIDGE_METHODS", "BasicHomeBridge"),
+        ("BASIC_FOREIGN_BRIDGE_METHODS", "BasicForeignBridge"),
+        ("FEE_MANAGER_METHODS", "FeeManager"),
+        ("PAUSABLE_METHODS", "Pausable"),
+        ("BRIDGE_LIMITS_METHODS", "BridgeLimits"),
+    ];
+
+    /// Returns the names of every `const *_METHODS` in `files`.
+    fn method_list_names(files: &[syn::File]) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for file in files {
+            find_method_list_names(&file.items, &mut names);
+        }
+        names
+    }
+
+    fn find_method_list_names(items: &[Item], names: &mut BTreeSet<String>) {
+        for item in items {
+            match item {
+                Item::Const(item) if item.ident.to_string().ends_with("_METHODS") => {
+                    names.insert(item.ident.to_string());
+                }
+                Item::Mod(item) => {
+                    if let Some((_, items)) = &item.content {
+                        find_method_list_names(items, names);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the signatures of the functions of `endpoint`.
+    fn functions(endpoint: &Endpoint) -> BTreeSet<String> {
+        endpoint
+            .entries
+            .iter()
+            .filter(|entry| entry["type"] == "function")
+            .map(|entry| signature(entry)["function ".len()..].to_string())
+            .collect()
+    }
+
+    /// Returns the strings in `const NAME: &[&str] = &[...]`.
+    fn method_list(files: &[syn::File], name: &str) -> BTreeSet<String> {
+        for file in files {
+            for item in &file.items {
+                let item = match item {
+                    Item::Const(item) if item.ident == name => item,
+                    _ => continue,
+                };
+                let array = match &*item.expr {
+                    Expr::Reference(reference) => match &*reference.expr {
+                        Expr::Array(array) => array,
+                        _ => break,
+                    },
+                    _ => break,
+                };
+                return array
+                    .elems
+                    .iter()
+                    .map(|elem| match elem {
+                        Expr::Lit(lit) => match &lit.lit {
+                            Lit::Str(s) => s.value(),
+                            _ => panic!("{}: not a string", name),
+                        },
+                        _ => panic!("{}: not a literal", name),
+                    })
+                    .collect();
+            }
+        }
+        panic!("no method list named {}", name)
+    }
+
+    fn endpoint<'a>(endpoints: &'a [Endpoint], name: &str) -> &'a Endpoint {
+        endpoints
+            .iter()
+            .find(|endpoint| endpoint.name == name)
+            .unwrap_or_else(|| panic!("no endpoint named {}", name))
+    }
+
+    fn abi(name: &str) -> Vec<Value> {
+        let contract = CONTRACTS
+            .iter()
+            .find(|contract| contract.name == name)
+            .unwrap();
+        match contract_abi(contract, &read_endpoints().unwrap()).unwrap() {
+            Value::Array(abi) => abi,
+            _ => unreachable!(),
+        }
+    }
+
+    fn event(abi: &[Value], signature: &str) -> Value {
+        abi.iter()
+            .find(|entry| super::signature(entry) == signature)
+            .unwrap_or_else(|| panic!("no {}", signature))
+            .clone()
+    }
+
+    #[test]
+    fn methods_match_dispatch() {
+        let mut files = Vec::new();
+        read_sources(&contracts_dir(), &mut files).unwrap();
+        let endpoints = read_endpoints().unwrap();
+        for &(list, name) in METHOD_LISTS {
+            assert_eq!(
+                functions(endpoint(&endpoints, name)),
+                method_list(&files, list),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn every_method_list_is_checked() {
+        let mut files = Vec::new();
+        read_sources(&contracts_dir(), &mut files).unwrap();
+        let checked: BTreeSet<String> = METHOD_LISTS
+            .iter()
+            .map(|&(list, _)| list.to_string())
+            .collect();
+        assert_eq!(method_list_names(&files), checked);
+    }
+
+    /// Every endpoint that a contract dispatches to before falling through
+    /// to its last one must be selected by a checked method list, and the
+    /// last one must not have a method that an earlier one shadows.
+    /// Together with `methods_match_dispatch`, this makes the ABI of every
+    /// contract, mode-specific endpoints included, match what `call` does.
+    #[test]
+    fn every_contract_matches_dispatch() {
+        let endpoints = read_endpoints().unwrap();
+        for contract in CONTRACTS {
+            let (last, dispatched) = contract.endpoints.split_last().unwrap();
+            let mut shadowed = BTreeSet::new();
+            for &name in dispatched {
+                assert!(
+                    METHOD_LISTS.iter().any(|&(_, endpoint)| endpoint == name),
+                    "{}: {} has no method list",
+                    contract.name,
+                    name
+                );
+                shadowed.extend(functions(endpoint(&endpoints, name)));
+            }
+            let unreachable: Vec<_> = functions(endpoint(&endpoints, last))
+                .intersection(&shadowed)
+                .cloned()
+                .collect();
+            assert!(
+                unreachable.is_empty(),
+                "{}: {} is shadowed by an earlier endpoint",
+                contract.name,
+                unreachable.join(", ")
+            );
+        }
+    }
+
+    #[test]
+    fn every_contract_builds() {
+        let endpoints = read_endpoints().unwrap();
+        for contract in CONTRACTS {
+            contract_abi(contract, &endpoints).unwrap();
+        }
+    }
+
+    #[test]
+    fn transfer_indexes_from_and_to() {
+        assert_eq!(
+            event(
+                &abi("ERC677BridgeToken"),
+                "event Transfer(address,address,uint256)"
+            ),
+            json!({
+                "type": "event",
+                "name": "Transfer",
+                "inputs": [
+                    { "name": "from", "type": "address", "indexed": true },
+                    { "name": "to", "type": "address", "indexed": true },
+                    { "name": "value", "type": "uint256", "indexed": false },
+                ],
+                "anonymous": false,
+            })
+        );
+    }
+
+    #[test]
+    fn role_granted_indexes_every_input() {
+        assert_eq!(
+            event(
+                &abi("BridgeValidators"),
+                "event RoleGranted(bytes32,address,address)"
+            ),
+            json!({
+                "type": "event",
+                "name": "RoleGranted",
+                "inputs": [
+                    { "name": "role", "type": "bytes32", "indexed": true },
+                    { "name": "account", "type": "address", "indexed": true },
+                    { "name": "sender", "type": "address", "indexed": true },
+                ],
+                "anonymous": false,
+            })
+        );
+    }
+}
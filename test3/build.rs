@@ -15,32 +15,310 @@
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <https://www.gnu.org/licenses/>.
 #![forbid(warnings)]
-use std::{env, fs::File, io::Write, path::PathBuf};
+use std::{env, fs, fs::File, io::Write, path::PathBuf};
 extern crate tiny_keccak;
 use tiny_keccak::Keccak;
 
-/// Write the generated code to `f`.
-fn generate(f: &mut dyn Write) {
+/// How a value is kept in storage.
+#[derive(Clone, Copy)]
+enum Type {
+    /// A `U256`, big-endian.  Written `uint256` in a schema.
+    U256,
+    /// An `Address`, right-aligned as in Solidity.  Written `address`.
+    Address,
+    /// A `bool`.  Any nonzero slot reads as `true`, and `true` is written as
+    /// 1.  Written `bool`.
+    Bool,
+    /// A `Vec<u8>`, laid out as Solidity lays out `bytes`: the length in the
+    /// slot, and the data in 32-byte chunks starting at `keccak256(slot)`.
+    /// Written `bytes`.
+    Bytes,
+}
+
+/// The hash that turns the key of a mapping entry into its slot.  Written
+/// `sha3` or `keccak256` in a schema.
+#[derive(Clone, Copy)]
+enum Hash {
+    Sha3,
+    Keccak,
+}
+
+/// Where a variable lives.
+#[derive(Clone, Copy)]
+enum Layout {
+    /// A single value, at `sha3_256(key)`.  Written `scalar` in a schema.
+    Scalar,
+    /// A mapping from keys of the given type.  The slot of an entry is the
+    /// hash of the variable's key followed by the entry's key, packed as by
+    /// `abi.encodePacked`.  Written `mapping(KEY_TYPE,HASH)`.
+    Mapping(Type, Hash),
+}
+
+use self::{Hash::*, Layout::*, Type::*};
+
+/// A variable of a storage schema.  Each variable gets a `get_{name}` and a
+/// `set_{name}` accessor.
+struct Variable {
+    key: String,
+    name: String,
+    layout: Layout,
+    ty: Type,
+}
+
+/// The storage schemas, as `(schema, generated file)`.  A schema lives next
+/// to the contract that includes its accessors, and declares one variable
+/// per line as `KEY NAME LAYOUT TYPE`.  Blank lines and lines starting with
+/// `#` are ignored.
+const SCHEMAS: &[(&str, &str)] = &[
+    (
+        "src/upgradeable_contracts/bridge_validators.storage",
+        "bridge_validators_storage.rs",
+    ),
+    ("src/upgradeability/proxy.storage", "proxy_storage.rs"),
+];
+
+/// Roles of `AccessControl`, as `(name, constant name)`.  As in Solidity, the
+/// identifier of a role is `keccak256(name)`.
+const ROLES: &[(&str, &str)] = &[
+    ("UPGRADER_ROLE", "UPGRADER_ROLE"),
+    ("LIMITS_MANAGER_ROLE", "LIMITS_MANAGER_ROLE"),
+    ("PAUSER_ROLE", "PAUSER_ROLE"),
+    ("VALIDATOR_MANAGER_ROLE", "VALIDATOR_MANAGER_ROLE"),
+];
+
+/// Write the role identifiers to `f`.
+fn generate_roles(f: &mut dyn Write) {
     let mut hash = [0; 32];
-    for (i, j) in &[
-        ("deployedAtBlock", "DEPLOYED_AT_BLOCK"),
-        ("requiredSignatures", "REQUIRED_SIGNATURES"),
-        ("validatorCount", "VALIDATOR_COUNT"),
-        ("isInitialized", "IS_INITIALIZED"),
-        ("owner", "OWNER",)
-    ] {
-        let mut q = Keccak::new_sha3_256();
-        q.update(i.as_bytes());
+    for (name, cname) in ROLES {
+        let mut q = Keccak::new_keccak256();
+        q.update(name.as_bytes());
         q.finalize(&mut hash);
-        write!(
+        writeln!(
             f,
-            "const {cname}: [u8;32] = {hash:?};\
-            #[allow(non_snake_case)]fn get_{i}()->[u8;32]{{read({typeconv}({cname}))}}\
-            #[allow(non_snake_case)]fn set_{i}(s:[u8;32]){{write({typeconv}({cname}),&s)}}\n",
-            cname = j,
+            "/// `keccak256(\"{name}\")`\npub const {cname}: [u8; 32] = {hash:?};",
+            name = name,
+            cname = cname,
             hash = hash,
-            i = i,
-            typeconv = "&pwasm_abi::types::H256::from",
+        )
+        .expect("I/O error in build script");
+    }
+}
+
+impl Type {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "uint256" => Some(U256),
+            "address" => Some(Address),
+            "bool" => Some(Bool),
+            "bytes" => Some(Bytes),
+            _ => None,
+        }
+    }
+
+    /// The Rust type of a value, as taken by a setter.
+    fn rust_type(self) -> &'static str {
+        match self {
+            U256 => "pwasm_std::types::U256",
+            Address => "pwasm_std::types::Address",
+            Bool => "bool",
+            Bytes => "&[u8]",
+        }
+    }
+
+    /// The Rust type of a value, as returned by a getter.
+    fn owned_rust_type(self) -> &'static str {
+        match self {
+            Bytes => "pwasm_std::Vec<u8>",
+            _ => self.rust_type(),
+        }
+    }
+
+    /// An expression that packs `value` as `abi.encodePacked` does.
+    fn packed(self, value: &str) -> String {
+        match self {
+            U256 => format!("&<[u8; 32]>::from({})[..]", value),
+            Address => format!("&{}[..]", value),
+            Bool => format!("&[{} as u8][..]", value),
+            Bytes => value.to_string(),
+        }
+    }
+
+    /// An expression that reads a value from the slot `slot`.
+    fn read(self, slot: &str) -> String {
+        let word = format!("crate::ext::read(&{})", slot);
+        match self {
+            U256 => format!("pwasm_std::types::U256::from({})", word),
+            Address => format!("pwasm_std::types::H256::from({}).into()", word),
+            Bool => format!("{} != [0; 32]", word),
+            Bytes => format!(
+                "crate::upgradeability::eternal_storage::read_bytes(&{})",
+                slot
+            ),
+        }
+    }
+
+    /// A statement that writes `value` to the slot `slot`.
+    fn write(self, slot: &str, value: &str) -> String {
+        let word = match self {
+            U256 => format!("{}.into()", value),
+            Address => format!("pwasm_std::types::H256::from({}).into()", value),
+            Bool => format!("pwasm_std::types::U256::from({} as u8).into()", value),
+            Bytes => {
+                return format!(
+                    "crate::upgradeability::eternal_storage::write_bytes(&{}, {})",
+                    slot, value
+                )
+            }
+        };
+        format!("crate::ext::write(&{}, &{})", slot, word)
+    }
+}
+
+impl Layout {
+    fn parse(s: &str) -> Option<Self> {
+        if s == "scalar" {
+            return Some(Scalar);
+        }
+        if !s.starts_with("mapping(") || !s.ends_with(')') {
+            return None;
+        }
+        let mut args = s["mapping(".len()..s.len() - 1].split(',');
+        let key_type = Type::parse(args.next()?)?;
+        let hash = match args.next()? {
+            "sha3" => Sha3,
+            "keccak256" => Keccak,
+            _ => return None,
+        };
+        match args.next() {
+            None => Some(Mapping(key_type, hash)),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Parses the schema at `path`.
+///
+/// # Panics
+///
+/// Panics, naming the offending line, if the schema is malformed.
+fn parse_schema(path: &str) -> Vec<Variable> {
+    let schema = fs::read_to_string(path).expect("cannot read storage schema");
+    let mut variables: Vec<Variable> = Vec::new();
+    for (number, line) in schema.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let error = |what: &str| format!("{}:{}: {}", path, number + 1, what);
+        let (key, name, layout, ty) = match fields[..] {
+            [key, name, layout, ty] => (key, name, layout, ty),
+            _ => panic!("{}", error("expected `KEY NAME LAYOUT TYPE`")),
+        };
+        // The name becomes part of the names of the accessors.
+        let mut chars = name.chars();
+        let is_identifier = match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() => {
+                chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            }
+            _ => false,
+        };
+        if !is_identifier {
+            panic!("{}", error("name must be an identifier"))
+        }
+        if variables.iter().any(|variable| variable.name == name) {
+            panic!("{}", error("duplicate name"))
+        }
+        if variables.iter().any(|variable| variable.key == key) {
+            panic!("{}", error("duplicate key"))
+        }
+        variables.push(Variable {
+            key: key.to_string(),
+            name: name.to_string(),
+            layout: Layout::parse(layout).unwrap_or_else(|| panic!("{}", error("unknown layout"))),
+            ty: Type::parse(ty).unwrap_or_else(|| panic!("{}", error("unknown type"))),
+        });
+    }
+    variables
+}
+
+/// Converts a camel-case name, such as `isInitialized`, to upper snake case.
+fn constant_name(name: &str) -> String {
+    let mut cname = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            cname.push('_');
+        }
+        cname.push(c.to_ascii_uppercase());
+    }
+    cname
+}
+
+/// Write the accessors of the variables in `schema` to `f`.
+fn generate(f: &mut dyn Write, schema: &[Variable]) {
+    let mut hash = [0; 32];
+    for &Variable {
+        ref key,
+        ref name,
+        layout,
+        ty,
+    } in schema
+    {
+        let (params, slot) = match layout {
+            Scalar => {
+                let mut q = Keccak::new_sha3_256();
+                q.update(key.as_bytes());
+                q.finalize(&mut hash);
+                let cname = constant_name(name);
+                writeln!(f, "const {}: [u8; 32] = {:?};", cname, hash)
+                    .expect("I/O error in build script");
+                (
+                    String::new(),
+                    format!("pwasm_std::types::H256::from({})", cname),
+                )
+            }
+            Mapping(key_type, hash) => {
+                writeln!(
+                    f,
+                    "#[allow(non_snake_case)]\
+                    fn {name}_slot(key: {key_type}) -> pwasm_std::types::H256 {{\
+                    let mut q = tiny_keccak::Keccak::{new}();\
+                    q.update({key:?}.as_bytes());\
+                    q.update({packed});\
+                    let mut hash = [0; 32];\
+                    q.finalize(&mut hash);\
+                    hash.into()\
+                    }}",
+                    name = name,
+                    key_type = key_type.rust_type(),
+                    new = match hash {
+                        Sha3 => "new_sha3_256",
+                        Keccak => "new_keccak256",
+                    },
+                    key = key,
+                    packed = key_type.packed("key"),
+                )
+                .expect("I/O error in build script");
+                (
+                    format!("key: {}", key_type.rust_type()),
+                    format!("{}_slot(key)", name),
+                )
+            }
+        };
+        let comma = if params.is_empty() { "" } else { ", " };
+        writeln!(
+            f,
+            "#[allow(dead_code, non_snake_case)]\
+            fn get_{name}({params}) -> {owned_type} {{ {read} }}\
+            #[allow(dead_code, non_snake_case)]\
+            fn set_{name}({params}{comma}value: {value_type}) {{ {write} }}",
+            name = name,
+            params = params,
+            comma = comma,
+            owned_type = ty.owned_rust_type(),
+            value_type = ty.rust_type(),
+            read = ty.read(&slot),
+            write = ty.write(&slot, "value"),
         )
         .expect("I/O error in build script");
     }
@@ -49,8 +327,14 @@ fn generate(f: &mut dyn Write) {
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     let manifest_path = env::var("OUT_DIR").expect("cargo should have set this");
+    for (schema, file) in SCHEMAS {
+        println!("cargo:rerun-if-changed={}", schema);
+        let mut path = PathBuf::from(&manifest_path);
+        path.push(file);
+        let mut f = File::create(path).expect("cannot create generated file");
+        generate(&mut f, &parse_schema(schema));
+    }
     let mut path = PathBuf::from(&manifest_path);
-    path.push("hashes.rs");
-    let mut f = File::create(path).expect("cannot create constants.rs");
-    generate(&mut f);
+    path.push("roles.rs");
+    generate_roles(&mut File::create(path).expect("cannot create generated file"));
 }
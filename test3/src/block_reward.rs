@@ -14,11 +14,58 @@
 //
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+use crate::ext::gas_left;
+use pwasm_abi_derive::eth_abi;
 use pwasm_std::types::{Address, U256};
 
 pub trait IBlockReward {
     fn add_extra_receiver(&mut self, amount: U256, receiver: Address);
     fn minted_totally(&self) -> U256;
-    fn minted_totally_by_bridge(bridge: Address) -> U256;
+    fn minted_totally_by_bridge(&self, bridge: Address) -> U256;
     fn bridges_allowed_length(&self) -> U256;
 }
+
+/// The ABI of the block reward contract of a POA network.  Bridges listed in
+/// the block reward contract may ask it to mint native coins.
+#[eth_abi(BlockRewardEndpoint, BlockRewardClient)]
+pub trait BlockReward {
+    /// Mints `amount` native coins to `receiver` at the next block.
+    fn addExtraReceiver(&mut self, amount: U256, receiver: Address);
+
+    #[constant]
+    fn mintedTotally(&self) -> U256;
+
+    #[constant]
+    fn mintedTotallyByBridge(&self, bridge: Address) -> U256;
+
+    #[constant]
+    fn bridgesAllowedLength(&self) -> U256;
+}
+
+/// A block reward contract deployed at the given address.
+pub struct BlockRewardContract(pub Address);
+
+impl BlockRewardContract {
+    fn client(&self) -> BlockRewardClient {
+        BlockRewardClient::new(self.0).gas(gas_left())
+    }
+}
+
+impl IBlockReward for BlockRewardContract {
+    fn add_extra_receiver(&mut self, amount: U256, receiver: Address) {
+        self.client().addExtraReceiver(amount, receiver)
+    }
+
+    fn minted_totally(&self) -> U256 {
+        self.client().mintedTotally()
+    }
+
+    fn minted_totally_by_bridge(&self, bridge: Address) -> U256 {
+        self.client().mintedTotallyByBridge(bridge)
+    }
+
+    fn bridges_allowed_length(&self) -> U256 {
+        self.client().bridgesAllowedLength()
+    }
+}
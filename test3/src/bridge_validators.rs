@@ -14,10 +14,171 @@
 //
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <https://www.gnu.org/licenses/>.
-use pwasm_std::types::Address;
+use super::upgradeability::eternal_storage::{key, EternalStorage};
+use super::upgradeable_contracts::BridgeValidatorsClient;
+use crate::dispatch::method_id;
+use crate::error::{require, BridgeError};
+use crate::ext::{call, gas_left, return_data};
+use pwasm_std::{
+    types::{Address, U256},
+    Vec,
+};
 
 pub trait IBridgeValidators {
     fn is_validator(&self, validator: Address) -> bool;
     fn required_signatures(&self) -> usize;
     fn owner(&self) -> Address;
+    /// All current validators, or none if the validator contract cannot
+    /// list them.
+    fn validator_list(&self) -> Vec<Address>;
+    /// The address that fees earned by `validator` are paid to, or the zero
+    /// address if the validator contract does not track reward addresses.
+    fn reward_address(&self, validator: Address) -> Address;
+}
+
+/// The `BridgeValidators` contract used by a bridge.  Its address is kept in
+/// the bridge's eternal storage under `validatorContract`.
+pub struct ValidatorContract;
+
+impl EternalStorage for ValidatorContract {}
+
+impl ValidatorContract {
+    /// Returns the address of the validator contract.
+    pub fn address(&self) -> Address {
+        self.addressStorage(key(b"validatorContract", &[]))
+    }
+
+    /// Sets the address of the validator contract.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `validator_contract` is the zero address.
+    pub fn set_address(&mut self, validator_contract: Address) {
+        require(
+            validator_contract != Address::zero(),
+            BridgeError::ZeroAddress,
+        );
+        self.setAddressStorage(key(b"validatorContract", &[]), validator_contract)
+    }
+
+    fn client(&self) -> BridgeValidatorsClient {
+        BridgeValidatorsClient::new(self.address())
+    }
+
+    /// Calls the validator contract with `input`, returning its output, or
+    /// `None` if the call fails.  Validator contracts deployed before
+    /// `validatorList` and `getValidatorRewardAddress` existed reject calls
+    /// to them, and a bridge must keep working with those, so these methods
+    /// are not called through the client, which would revert.
+    fn try_call(&self, input: &[u8]) -> Option<Vec<u8>> {
+        call(gas_left(), &self.address(), U256::zero(), input, &mut []).ok()?;
+        Some(return_data())
+    }
+}
+
+/// Decodes the ABI encoding of an `address[]` return value.
+fn decode_addresses(data: &[u8]) -> Option<Vec<Address>> {
+    let word = |index: usize| {
+        data.get(32 * index..32 * (index + 1))
+            .map(U256::from_big_endian)
+    };
+    if word(0)? != U256::from(32) {
+        return None;
+    }
+    let length = word(1)?;
+    if length > U256::from(data.len() / 32) {
+        return None;
+    }
+    (0..length.low_u64() as usize)
+        .map(|index| {
+            data.get(64 + 32 * index + 12..64 + 32 * (index + 1))
+                .map(Address::from_slice)
+        })
+        .collect()
+}
+
+impl IBridgeValidators for ValidatorContract {
+    fn is_validator(&self, validator: Address) -> bool {
+        self.client().isValidator(validator)
+    }
+
+    fn required_signatures(&self) -> usize {
+        // `BridgeValidators` never lets this exceed `usize::MAX`
+        self.client().requiredSignatures().low_u64() as usize
+    }
+
+    fn owner(&self) -> Address {
+        self.client().owner()
+    }
+
+    fn validator_list(&self) -> Vec<Address> {
+        self.try_call(&method_id("validatorList()"))
+            .and_then(|output| decode_addresses(&output))
+            .unwrap_or_default()
+    }
+
+    fn reward_address(&self, validator: Address) -> Address {
+        let mut input = Vec::with_capacity(36);
+        input.extend_from_slice(&method_id("getValidatorRewardAddress(address)"));
+        input.extend_from_slice(&[0; 12]);
+        input.extend_from_slice(validator.as_ref());
+        match self.try_call(&input) {
+            Some(ref output) if output.len() == 32 => Address::from_slice(&output[12..]),
+            _ => Address::zero(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::native;
+    use std::vec;
+
+    fn validator_contract() -> ValidatorContract {
+        native::reset();
+        let mut contract = ValidatorContract;
+        contract.set_address(Address::from([9; 20]));
+        contract
+    }
+
+    #[test]
+    fn lists_validators() {
+        let contract = validator_contract();
+        native::with(|ext| {
+            ext.stub_call(Address::from([9; 20]), |_| {
+                let mut output = vec![0; 128];
+                output[31] = 32;
+                output[63] = 2;
+                output[76..96].copy_from_slice(&[1; 20]);
+                output[108..].copy_from_slice(&[2; 20]);
+                Ok(output)
+            })
+        });
+        assert_eq!(
+            contract.validator_list(),
+            vec![Address::from([1; 20]), Address::from([2; 20])]
+        );
+    }
+
+    #[test]
+    fn lists_no_validators_if_contract_cannot() {
+        let contract = validator_contract();
+        native::with(|ext| ext.stub_call(Address::from([9; 20]), |_| Err(Vec::new())));
+        assert!(contract.validator_list().is_empty());
+        assert!(contract.reward_address(Address::from([1; 20])).is_zero());
+    }
+
+    #[test]
+    fn rejects_malformed_address_lists() {
+        let mut data = vec![0; 64];
+        data[31] = 32;
+        data[63] = 1;
+        assert_eq!(decode_addresses(&data), None);
+        data[31] = 64;
+        data[63] = 0;
+        assert_eq!(decode_addresses(&data), None);
+        data[31] = 32;
+        assert_eq!(decode_addresses(&data), Some(Vec::new()));
+    }
 }
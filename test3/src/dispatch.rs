@@ -0,0 +1,40 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Routing of calls for contracts whose ABI is split across several
+//! `#[eth_abi]` endpoints.
+//!
+//! An endpoint's `dispatch` panics on a method it does not know, so before
+//! handing the input to an endpoint the caller must check that the endpoint
+//! implements the method being called.
+use pwasm_std::keccak;
+
+/// Computes the method id of `signature`, which must be in canonical form,
+/// e.g. `"upgradeTo(uint256,address)"`.
+pub fn method_id(signature: &str) -> [u8; 4] {
+    let hash = keccak(signature.as_bytes());
+    let mut id = [0; 4];
+    id.copy_from_slice(&hash[..4]);
+    id
+}
+
+/// Returns `true` if `input` is a call to one of the methods in `signatures`.
+pub fn calls_any(input: &[u8], signatures: &[&str]) -> bool {
+    input.len() >= 4
+        && signatures
+            .iter()
+            .any(|signature| method_id(signature)[..] == input[..4])
+}
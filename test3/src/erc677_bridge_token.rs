@@ -0,0 +1,374 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+//! The bridgeable token: an ERC20 token that also implements ERC677's
+//! `transferAndCall`, and that the bridge contract (its owner) can mint and
+//! burn.
+//!
+//! Each interface is its own `#[eth_abi]` trait with no supertraits, so that a
+//! client can be derived for each of them.  The contract implements all of
+//! them, and `call` routes each method to the right endpoint.
+use super::upgradeability::eternal_storage::{key, EternalStorage};
+use super::upgradeable_contracts::ownable::{
+    OwnableInternal, OwnableTrait, OwnerRoles, OWNABLE_METHODS,
+};
+use crate::error::{require, revert, BridgeError};
+use crate::ext::{address, balance, call, extcodesize, gas_left, sender};
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::{
+    types::{Address, H256, U256},
+    Vec,
+};
+
+#[eth_abi(ERC20, ERC20Client)]
+pub trait IERC20 {
+    #[event]
+    fn Transfer(&mut self, indexed_from: Address, indexed_to: Address, value: U256);
+
+    #[event]
+    fn Approval(&mut self, indexed_owner: Address, indexed_spender: Address, value: U256);
+
+    #[constant]
+    fn totalSupply(&self) -> U256;
+
+    #[constant]
+    fn balanceOf(&self, who: Address) -> U256;
+
+    #[constant]
+    fn allowance(&self, owner: Address, spender: Address) -> U256;
+
+    fn transfer(&mut self, to: Address, value: U256) -> bool;
+
+    fn approve(&mut self, spender: Address, value: U256) -> bool;
+
+    fn transferFrom(&mut self, from: Address, to: Address, value: U256) -> bool;
+}
+
+#[eth_abi(ERC677, ERC677Client)]
+pub trait IERC677 {
+    #[event]
+    fn Transfer(&mut self, indexed_from: Address, indexed_to: Address, value: U256, data: Vec<u8>);
+
+    /// Transfers `value` tokens to `to`.  If `to` is a contract, then calls
+    /// `onTokenTransfer` on it, which must return `true`.  Externally owned
+    /// accounts cannot implement `onTokenTransfer`, so for them this is
+    /// just `transfer`.
+    fn transferAndCall(&mut self, to: Address, value: U256, data: Vec<u8>) -> bool;
+}
+
+/// Implemented by contracts that accept tokens through `transferAndCall`.
+#[eth_abi(ERC677Receiver, ERC677ReceiverClient)]
+pub trait IERC677Receiver {
+    fn onTokenTransfer(&mut self, from: Address, value: U256, data: Vec<u8>) -> bool;
+}
+
+#[eth_abi(BurnableMintableERC677Token, BurnableMintableERC677TokenClient)]
+pub trait IBurnableMintableERC677Token {
+    #[event]
+    fn Mint(&mut self, indexed_to: Address, amount: U256);
+
+    #[event]
+    fn Burn(&mut self, indexed_burner: Address, value: U256);
+
+    /// The constructor sets the owner of the token to the sender account.
+    fn constructor(&mut self);
+
+    /// Mints `amount` new tokens to `to`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not the owner.
+    fn mint(&mut self, to: Address, amount: U256) -> bool;
+
+    /// Burns `value` of the sender's tokens.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sender holds fewer than `value` tokens.
+    fn burn(&mut self, value: U256);
+
+    /// Sends the tokens of the ERC20 contract `token` that this contract
+    /// holds to `to`.  If `token` is the zero address, sends native coins
+    /// instead.  This recovers funds that were sent to the token by mistake.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not the owner, or if `to` is the zero
+    /// address.
+    fn claimTokens(&mut self, token: Address, to: Address);
+}
+
+pub const ERC20_METHODS: &[&str] = &[
+    "totalSupply()",
+    "balanceOf(address)",
+    "allowance(address,address)",
+    "transfer(address,uint256)",
+    "approve(address,uint256)",
+    "transferFrom(address,address,uint256)",
+];
+
+pub const ERC677_METHODS: &[&str] = &["transferAndCall(address,uint256,bytes)"];
+
+pub const ERC677_RECEIVER_METHODS: &[&str] = &["onTokenTransfer(address,uint256,bytes)"];
+
+pub const TOKEN_METHODS: &[&str] = &[
+    "mint(address,uint256)",
+    "burn(uint256)",
+    "claimTokens(address,address)",
+];
+
+pub struct ERC677BridgeToken;
+
+impl EternalStorage for ERC677BridgeToken {}
+
+impl OwnableTrait for ERC677BridgeToken {}
+
+impl OwnerRoles for ERC677BridgeToken {}
+
+/// Tells whether there is code at `account`.  Like Solidity's
+/// `extcodesize` check, this is false for a contract that is still running
+/// its constructor.
+fn is_contract(account: Address) -> bool {
+    extcodesize(&account) > 0
+}
+
+fn balance_key(who: Address) -> H256 {
+    key(b"balances", who.as_ref())
+}
+
+fn allowance_key(owner: Address, spender: Address) -> H256 {
+    let mut buf = [0; 40];
+    buf[..20].copy_from_slice(owner.as_ref());
+    buf[20..].copy_from_slice(spender.as_ref());
+    key(b"allowed", &buf)
+}
+
+impl ERC677BridgeToken {
+    /// Moves `value` tokens from `from` to `to`, without checking allowances.
+    fn move_tokens(&mut self, from: Address, to: Address, value: U256) {
+        require(to != Address::zero(), BridgeError::ZeroAddress);
+        require(to != address(), BridgeError::TransferToToken);
+        let from_balance = self.balanceOf(from);
+        require(from_balance >= value, BridgeError::InsufficientBalance);
+        self.setUintStorage(balance_key(from), from_balance - value);
+        let to_balance = self.balanceOf(to);
+        self.setUintStorage(balance_key(to), to_balance + value);
+        IERC20::Transfer(self, from, to, value);
+    }
+}
+
+impl IERC20 for ERC677BridgeToken {
+    fn totalSupply(&self) -> U256 {
+        self.uintStorage(key(b"totalSupply", &[]))
+    }
+
+    fn balanceOf(&self, who: Address) -> U256 {
+        self.uintStorage(balance_key(who))
+    }
+
+    fn allowance(&self, owner: Address, spender: Address) -> U256 {
+        self.uintStorage(allowance_key(owner, spender))
+    }
+
+    fn transfer(&mut self, to: Address, value: U256) -> bool {
+        self.move_tokens(sender(), to, value);
+        true
+    }
+
+    fn approve(&mut self, spender: Address, value: U256) -> bool {
+        let owner = sender();
+        self.setUintStorage(allowance_key(owner, spender), value);
+        self.Approval(owner, spender, value);
+        true
+    }
+
+    fn transferFrom(&mut self, from: Address, to: Address, value: U256) -> bool {
+        let spender = sender();
+        let allowed = self.allowance(from, spender);
+        require(allowed >= value, BridgeError::InsufficientAllowance);
+        self.setUintStorage(allowance_key(from, spender), allowed - value);
+        self.move_tokens(from, to, value);
+        true
+    }
+}
+
+impl IERC677 for ERC677BridgeToken {
+    fn transferAndCall(&mut self, to: Address, value: U256, data: Vec<u8>) -> bool {
+        let from = sender();
+        self.move_tokens(from, to, value);
+        IERC677::Transfer(self, from, to, value, data.clone());
+        if is_contract(to) {
+            require(
+                ERC677ReceiverClient::new(to)
+                    .gas(gas_left())
+                    .onTokenTransfer(from, value, data),
+                BridgeError::TransferRejected,
+            );
+        }
+        true
+    }
+}
+
+impl IBurnableMintableERC677Token for ERC677BridgeToken {
+    fn constructor(&mut self) {
+        self.setOwner(sender())
+    }
+
+    fn mint(&mut self, to: Address, amount: U256) -> bool {
+        self.onlyOwner();
+        require(to != Address::zero(), BridgeError::ZeroAddress);
+        let (total_supply, overflow) = self.totalSupply().overflowing_add(amount);
+        require(!overflow, BridgeError::Overflow);
+        self.setUintStorage(key(b"totalSupply", &[]), total_supply);
+        let to_balance = self.balanceOf(to);
+        self.setUintStorage(balance_key(to), to_balance + amount);
+        self.Mint(to, amount);
+        IERC20::Transfer(self, Address::zero(), to, amount);
+        true
+    }
+
+    fn burn(&mut self, value: U256) {
+        let burner = sender();
+        let burner_balance = self.balanceOf(burner);
+        require(burner_balance >= value, BridgeError::InsufficientBalance);
+        self.setUintStorage(balance_key(burner), burner_balance - value);
+        let total_supply = self.totalSupply();
+        self.setUintStorage(key(b"totalSupply", &[]), total_supply - value);
+        self.Burn(burner, value);
+        IERC20::Transfer(self, burner, Address::zero(), value);
+    }
+
+    fn claimTokens(&mut self, token: Address, to: Address) {
+        self.onlyOwner();
+        require(to != Address::zero(), BridgeError::ZeroAddress);
+        if token == Address::zero() {
+            call(gas_left(), &to, balance(&address()), &[], &mut [])
+                .unwrap_or_else(|_| revert(BridgeError::TransferFailed));
+        } else {
+            let mut token = ERC20Client::new(token).gas(gas_left());
+            let amount = token.balanceOf(address());
+            require(token.transfer(to, amount), BridgeError::TransferFailed);
+        }
+    }
+}
+
+/// Runs the token on the call data `input`, returning its output.
+#[cfg(any(feature = "erc677-token", not(target_arch = "wasm32")))]
+pub fn run(input: &[u8]) -> Vec<u8> {
+    use super::dispatch::calls_any;
+    use super::upgradeable_contracts::ownable::Ownable;
+    use pwasm_abi::eth::EndpointInterface;
+    if calls_any(input, ERC20_METHODS) {
+        ERC20::new(ERC677BridgeToken).dispatch(input)
+    } else if calls_any(input, ERC677_METHODS) {
+        ERC677::new(ERC677BridgeToken).dispatch(input)
+    } else if calls_any(input, OWNABLE_METHODS) {
+        Ownable::new(ERC677BridgeToken).dispatch(input)
+    } else {
+        BurnableMintableERC677Token::new(ERC677BridgeToken).dispatch(input)
+    }
+}
+
+/// Runs the constructor of the token on `input`.
+#[cfg(any(feature = "erc677-token", not(target_arch = "wasm32")))]
+pub fn construct(input: &[u8]) {
+    use pwasm_abi::eth::EndpointInterface;
+    BurnableMintableERC677Token::new(ERC677BridgeToken).dispatch_ctor(input)
+}
+
+#[cfg(feature = "erc677-token")]
+#[no_mangle]
+pub fn call() {
+    crate::ext::ret(&run(&crate::ext::input()));
+}
+
+#[cfg(feature = "erc677-token")]
+#[no_mangle]
+pub fn deploy() {
+    construct(&crate::ext::input());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatch::method_id;
+    use crate::ext::native::{self, Panic};
+    use std::{panic::catch_unwind, vec};
+
+    fn holder() -> Address {
+        Address::from([1; 20])
+    }
+
+    /// Deploys the token from `holder`, and mints it 100 tokens.
+    fn setup() {
+        native::reset();
+        native::with(|ext| ext.sender = holder());
+        ERC677BridgeToken.constructor();
+        ERC677BridgeToken.mint(holder(), 100.into());
+    }
+
+    /// A contract whose `onTokenTransfer` returns `accepts`.
+    fn receiver(accepts: bool) -> Address {
+        let receiver = Address::from([2; 20]);
+        native::with(|ext| {
+            ext.code_sizes.insert(receiver, 1);
+            ext.stub_call(receiver, move |_| {
+                let mut output = vec![0; 32];
+                output[31] = accepts as u8;
+                Ok(output)
+            });
+        });
+        receiver
+    }
+
+    #[test]
+    fn transfer_and_call_skips_callback_for_accounts_without_code() {
+        setup();
+        let to = Address::from([3; 20]);
+        assert!(ERC677BridgeToken.transferAndCall(to, 10.into(), vec![1, 2, 3]));
+        assert_eq!(ERC677BridgeToken.balanceOf(to), 10.into());
+        assert_eq!(ERC677BridgeToken.balanceOf(holder()), 90.into());
+        assert!(native::with(|ext| ext.calls.is_empty()));
+    }
+
+    #[test]
+    fn transfer_and_call_calls_contracts() {
+        setup();
+        let to = receiver(true);
+        assert!(ERC677BridgeToken.transferAndCall(to, 10.into(), vec![1, 2, 3]));
+        assert_eq!(ERC677BridgeToken.balanceOf(to), 10.into());
+        let calls = native::with(|ext| ext.calls.clone());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].address, to);
+        assert_eq!(
+            calls[0].input[..4],
+            method_id("onTokenTransfer(address,uint256,bytes)")
+        );
+    }
+
+    #[test]
+    fn transfer_and_call_reverts_if_contract_rejects() {
+        setup();
+        let to = receiver(false);
+        let payload =
+            catch_unwind(|| ERC677BridgeToken.transferAndCall(to, 10.into(), vec![])).unwrap_err();
+        assert_eq!(
+            BridgeError::decode(&payload.downcast::<Panic>().unwrap().0),
+            Some(BridgeError::TransferRejected)
+        );
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Why a call failed.
+//!
+//! Every failure of a contract reverts with a [`BridgeError`], ABI-encoded
+//! the way Solidity encodes `revert("...")`: the selector of
+//! `Error(string)`, followed by the reason.  The reason starts with the
+//! error code, as in `"E003: This method can only be called by a
+//! validator"`, so that relayers and UIs can tell errors apart without
+//! matching on the message.  [`BridgeError::decode`] recovers the error from
+//! revert data.
+//!
+//! Codes are part of the ABI.  They must never be reused or renumbered.
+use crate::dispatch::method_id;
+use pwasm_std::{types::U256, Vec};
+
+macro_rules! bridge_errors {
+    ($($(#[$attr:meta])* $name:ident = $code:literal => $message:expr,)*) => {
+        /// A reason for a call to fail.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum BridgeError {
+            $($(#[$attr])* $name = $code,)*
+        }
+
+        impl BridgeError {
+            /// Returns the error with code `code`, if there is one.
+            pub fn from_code(code: u16) -> Option<Self> {
+                match code {
+                    $($code => Some(BridgeError::$name),)*
+                    _ => None,
+                }
+            }
+
+            /// A description of the error, without its code.
+            pub fn message(self) -> &'static str {
+                match self {
+                    $(BridgeError::$name => $message,)*
+                }
+            }
+        }
+    };
+}
+
+bridge_errors! {
+    // Access control
+    NotOwner = 1 => "This method can only be called by the contract owner",
+    MissingRole = 2 => "This method can only be called by a member of the required role",
+    NotValidator = 3 => "This method can only be called by a validator",
+    NotBridgedToken = 4 => "Only the bridged token may call this",
+    NotPendingOwner = 5 => "Only the pending owner can claim ownership",
+    NoOwnershipTransferPending = 6 => "No ownership transfer pending",
+    RenounceForOther = 7 => "Can only renounce roles for self",
+
+    // Arguments and contract state
+    ZeroAddress = 20 => "Address must not be zero",
+    ZeroValue = 21 => "Cannot transfer zero value",
+    AlreadyInitialized = 22 => "Contract already initialized",
+    Paused = 23 => "Contract is paused",
+    NotPaused = 24 => "Contract is not paused",
+    CorruptStorage = 25 => "Corrupt storage",
+
+    // Validators
+    AlreadyValidator = 40 => "Already a validator",
+    UnknownValidator = 41 => "Not a validator",
+    InvalidRequiredSignatures = 42 => "Invalid number of required signatures",
+    ReservedAddress = 43 => "Address is reserved",
+    AlreadyLinked = 44 => "Validator already in the validator list",
+
+    // Messages and signatures
+    InvalidMessageLength = 60 => "Invalid message length (must be 104 bytes)",
+    InvalidSignatureLength = 61 => "Invalid signature length (must be 65 bytes)",
+    InvalidSignature = 62 => "Invalid signature",
+    NotEnoughSignatures = 63 => "Not enough signatures",
+    DuplicateSignature = 64 => "Duplicate signature",
+    SignatureFromNonValidator = 65 => "Signature from non-validator",
+    MessageNotSignedBySender = 66 => "Message not signed by sender",
+    WrongContract = 67 => "Message is for a different contract",
+    AlreadyProcessed = 68 => "Message already processed",
+    AlreadyRelayed = 69 => "Message already relayed",
+
+    // Limits and fees
+    ExceedsLimits = 80 => "Transfer exceeds the limits",
+    ExceedsExecutionLimits = 81 => "Transfer exceeds the execution limits",
+    InvalidLimit = 82 => "Invalid limit",
+    InvalidFee = 83 => "Fee must be less than 100%",
+    Overflow = 84 => "Value too large",
+    NoFeeRecipients = 85 => "No validators to pay the fee to",
+
+    // Transfers
+    InsufficientBalance = 100 => "Insufficient balance",
+    InsufficientAllowance = 101 => "Insufficient allowance",
+    TransferToToken = 102 => "Cannot transfer to the token contract",
+    TransferFailed = 103 => "Transfer failed",
+    ExecutionFailed = 104 => "Failed to execute transfer",
+    BurnExceedsMinted = 105 => "Cannot burn more coins than were minted",
+    BlockRewardNotSet = 106 => "Block reward contract not set",
+    NotBlockReward = 107 => "Not a block reward contract",
+    CallFailed = 108 => "Call failed",
+    TransferRejected = 109 => "Recipient rejected the transfer",
+
+    // Upgrades
+    NoImplementation = 120 => "No implementation set",
+    SameImplementation = 121 => "Already using this implementation",
+    InvalidVersion = 122 => "Version must be greater than the latest version",
+    UnknownVersion = 123 => "Unknown version",
+    AlreadyAtVersion = 124 => "Already at this version",
+    UpgradeMustBeScheduled = 125 => "Upgrades must be scheduled",
+    NoUpgradeScheduled = 126 => "No upgrade scheduled",
+    UpgradeNotDue = 127 => "Upgrade is not yet due",
+    UpgradeTooSoon = 128 => "Upgrade must be scheduled at least upgradeDelay seconds ahead",
+    DelayTooLong = 129 => "Delay is too long",
+    NoDelayChangeScheduled = 130 => "No change of upgradeDelay scheduled",
+    DelayChangeNotDue = 131 => "Change of upgradeDelay is not yet due",
+
+    // Multisignature wallet
+    NotWallet = 140 => "This method can only be called by the wallet itself",
+    NotWalletOwner = 141 => "This method can only be called by an owner",
+    TransactionNotFound = 142 => "Transaction does not exist",
+    InvalidRequirement = 143 => "Invalid requirement",
+    DuplicateOwner = 144 => "Duplicate owner",
+    AlreadyOwner = 145 => "Already an owner",
+    UnknownOwner = 146 => "Not an owner",
+    LastOwner = 147 => "Cannot remove the last owner",
+    AlreadyConfirmed = 148 => "Already confirmed",
+    NotConfirmed = 149 => "Not confirmed",
+    AlreadyExecuted = 150 => "Already executed",
+}
+
+/// The signature of the revert data that Solidity produces for
+/// `revert(reason)`.
+const ERROR_SIGNATURE: &str = "Error(string)";
+
+/// The length of the code prefix of a reason, e.g. `"E003: "`.
+const PREFIX_LENGTH: usize = 6;
+
+impl BridgeError {
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+
+    /// The reason the call reverts with: the code, followed by the message.
+    pub fn reason(self) -> Vec<u8> {
+        let code = self.code();
+        let mut reason = Vec::with_capacity(PREFIX_LENGTH + self.message().len());
+        reason.push(b'E');
+        for &digit in &[code / 100, code / 10 % 10, code % 10] {
+            reason.push(b'0' + digit as u8);
+        }
+        reason.extend_from_slice(b": ");
+        reason.extend_from_slice(self.message().as_bytes());
+        reason
+    }
+
+    /// ABI-encodes the error as the revert data of `Error(string)`.
+    pub fn encode(self) -> Vec<u8> {
+        let reason = self.reason();
+        let padded_length = (reason.len() + 31) / 32 * 32;
+        let mut data = Vec::with_capacity(4 + 64 + padded_length);
+        data.extend_from_slice(&method_id(ERROR_SIGNATURE));
+        data.extend_from_slice(&<[u8; 32]>::from(U256::from(32)));
+        data.extend_from_slice(&<[u8; 32]>::from(U256::from(reason.len())));
+        data.extend_from_slice(&reason);
+        data.resize(4 + 64 + padded_length, 0);
+        data
+    }
+
+    /// Recovers the error from the revert data of a call.  Returns `None` if
+    /// the data is not an encoded `BridgeError`.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 + 64 || data[..4] != method_id(ERROR_SIGNATURE) {
+            return None;
+        }
+        // The string must directly follow its offset, as `encode` puts it.
+        if U256::from_big_endian(&data[4..36]) != U256::from(32) {
+            return None;
+        }
+        let length = U256::from_big_endian(&data[36..68]);
+        if length > U256::from(data.len() - 68) {
+            return None;
+        }
+        let reason = &data[68..68 + length.low_u64() as usize];
+        if reason.len() < PREFIX_LENGTH || reason[0] != b'E' || &reason[4..6] != b": " {
+            return None;
+        }
+        let mut code = 0;
+        for &digit in &reason[1..4] {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            code = code * 10 + u16::from(digit - b'0');
+        }
+        Self::from_code(code)
+    }
+}
+
+/// Stops execution, reverting with `error`.
+pub fn revert(error: BridgeError) -> ! {
+    crate::ext::panic(&error.encode())
+}
+
+/// Reverts with `error` unless `condition` holds.  This is `assert!` for
+/// failures that callers need to be told about.
+pub fn require(condition: bool, error: BridgeError) {
+    if !condition {
+        revert(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec;
+
+    /// What Solidity's `revert("E001: ...")` reverts with.
+    fn not_owner_data() -> Vec<u8> {
+        let reason: &[u8] = b"E001: This method can only be called by the contract owner";
+        let mut data = vec![0x08, 0xc3, 0x79, 0xa0];
+        data.extend_from_slice(&[0; 31]);
+        data.push(0x20);
+        data.extend_from_slice(&[0; 31]);
+        data.push(58);
+        data.extend_from_slice(reason);
+        data.extend_from_slice(&[0; 6]);
+        data
+    }
+
+    #[test]
+    fn encodes_like_solidity() {
+        assert_eq!(BridgeError::NotOwner.encode(), not_owner_data());
+        assert_eq!(
+            BridgeError::decode(&not_owner_data()),
+            Some(BridgeError::NotOwner)
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        for code in 0..1000 {
+            if let Some(error) = BridgeError::from_code(code) {
+                assert_eq!(error.code(), code);
+                assert_eq!(BridgeError::decode(&error.encode()), Some(error));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_data() {
+        let data = not_owner_data();
+        assert_eq!(BridgeError::decode(&data[..4 + 64]), None);
+        assert_eq!(BridgeError::decode(&[]), None);
+        let mut wrong_selector = data.clone();
+        wrong_selector[0] = 0;
+        assert_eq!(BridgeError::decode(&wrong_selector), None);
+        let mut wrong_offset = data.clone();
+        wrong_offset[35] = 0x40;
+        assert_eq!(BridgeError::decode(&wrong_offset), None);
+        let mut wrong_prefix = data;
+        wrong_prefix[68] = b'X';
+        assert_eq!(BridgeError::decode(&wrong_prefix), None);
+    }
+}
@@ -0,0 +1,37 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! The host functions the contracts call.
+//!
+//! On `wasm32` these are the imports provided by the Ethereum host, re-exported
+//! from `pwasm_ethereum`.  Everywhere else they are backed by an in-memory
+//! [`Externalities`](native::Externalities), so that the contracts can be
+//! run with `cargo test` on an ordinary host.  Contracts must call the host
+//! through this module rather than through `pwasm_ethereum` directly.
+#[cfg(target_arch = "wasm32")]
+pub use pwasm_ethereum::{
+    address, balance, block_number, call, call_code, extcodesize, gas_left, input, panic, read,
+    ret, return_data, sender, timestamp, value, write, Error,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::native::{
+    address, balance, block_number, call, call_code, extcodesize, gas_left, input, panic, read,
+    ret, return_data, sender, timestamp, value, write, Error,
+};
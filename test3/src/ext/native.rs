@@ -0,0 +1,353 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! In-memory externalities for running contracts natively.
+//!
+//! Each thread has its own [`Externalities`], which starts out empty and can
+//! be inspected and programmed through [`with`]: the caller, value, block,
+//! and storage seen by the contract, the logs it emits, and the results of
+//! the calls it makes.  A call to an address with no stub goes to the
+//! handler set with [`Externalities::handle_calls`], or, if there is none,
+//! succeeds and returns nothing.  As with `pwasm_ethereum::return_data`, the
+//! output of the last call, or the data it reverted with, is available from
+//! [`return_data`].
+//!
+//! Code generated by `pwasm_abi_derive` calls the `pwasm_ethereum` host
+//! imports directly to emit events and make calls through a client, so this
+//! module also defines those imports (`elog` and `ccall`) in terms of the
+//! same externalities.
+//!
+//! Outside of the tests, nothing inspects the externalities, so the parts of
+//! this module that only the tests use are allowed to be dead there.
+use pwasm_std::types::{Address, H256, U256};
+use std::{boxed::Box, cell::RefCell, collections::BTreeMap, panic, rc::Rc, slice, vec::Vec};
+
+/// The error returned when a call fails.
+#[derive(Debug)]
+pub struct Error;
+
+/// A log emitted by a contract.
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    /// The contract that emitted the log.
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+/// How a contract called another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CallKind {
+    /// A message call, made with `call`.
+    Call,
+    /// A delegate call, made with `call_code`: the callee's code runs with
+    /// the caller's storage, sender, and value.
+    CallCode,
+}
+
+/// A call made by a contract.
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallRecord {
+    pub kind: CallKind,
+    pub gas: u64,
+    pub address: Address,
+    pub value: U256,
+    pub input: Vec<u8>,
+}
+
+/// Handles calls.  Returns the output of the call, or the data it reverts
+/// with if it fails.  A stub may itself use the externalities, and make
+/// further calls.
+pub type CallStub = Rc<dyn Fn(&CallRecord) -> Result<Vec<u8>, Vec<u8>>>;
+
+/// The payload of the panic that [`ret`] unwinds with, as there is no host
+/// to return to.
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Debug, PartialEq)]
+pub struct Return(pub Vec<u8>);
+
+/// The payload of the panic that [`panic`] unwinds with: the data that the
+/// call reverts with.
+#[derive(Debug, PartialEq)]
+pub struct Panic(pub Vec<u8>);
+
+/// The state of the host, as seen by a contract.
+#[cfg_attr(not(test), allow(dead_code))]
+pub struct Externalities {
+    pub storage: BTreeMap<H256, [u8; 32]>,
+    pub sender: Address,
+    pub value: U256,
+    pub address: Address,
+    pub balances: BTreeMap<Address, U256>,
+    /// The size of the code of every account that has code.
+    pub code_sizes: BTreeMap<Address, usize>,
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub gas_left: u64,
+    pub input: Vec<u8>,
+    /// Every log emitted, in order.
+    pub logs: Vec<LogEntry>,
+    /// Every call made, in order.
+    pub calls: Vec<CallRecord>,
+    /// The output of the last call made, or the data it reverted with.
+    pub return_data: Vec<u8>,
+    stubs: BTreeMap<Address, CallStub>,
+    handler: Option<CallStub>,
+}
+
+impl Default for Externalities {
+    fn default() -> Self {
+        Externalities {
+            storage: BTreeMap::new(),
+            sender: Address::zero(),
+            value: U256::zero(),
+            address: Address::zero(),
+            balances: BTreeMap::new(),
+            code_sizes: BTreeMap::new(),
+            block_number: 0,
+            timestamp: 0,
+            gas_left: 8_000_000,
+            input: Vec::new(),
+            logs: Vec::new(),
+            calls: Vec::new(),
+            return_data: Vec::new(),
+            stubs: BTreeMap::new(),
+            handler: None,
+        }
+    }
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl Externalities {
+    /// Handles calls to `address` with `stub`, replacing any previous stub.
+    pub fn stub_call<F>(&mut self, address: Address, stub: F)
+    where
+        F: Fn(&CallRecord) -> Result<Vec<u8>, Vec<u8>> + 'static,
+    {
+        self.stubs.insert(address, Rc::new(stub));
+    }
+
+    /// Handles calls to every address that has no stub with `handler`.
+    pub fn handle_calls<F>(&mut self, handler: F)
+    where
+        F: Fn(&CallRecord) -> Result<Vec<u8>, Vec<u8>> + 'static,
+    {
+        self.handler = Some(Rc::new(handler));
+    }
+}
+
+thread_local! {
+    static EXTERNALITIES: RefCell<Externalities> = RefCell::new(Externalities::default());
+}
+
+/// Runs `f` on the externalities of this thread.
+///
+/// # Panics
+///
+/// Panics if called from within `f`, or from a call stub.
+pub fn with<R, F: FnOnce(&mut Externalities) -> R>(f: F) -> R {
+    EXTERNALITIES.with(|ext| f(&mut ext.borrow_mut()))
+}
+
+/// Replaces the externalities of this thread with empty ones.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn reset() {
+    with(|ext| *ext = Externalities::default())
+}
+
+pub fn read(key: &H256) -> [u8; 32] {
+    with(|ext| ext.storage.get(key).cloned().unwrap_or([0; 32]))
+}
+
+pub fn write(key: &H256, value: &[u8; 32]) {
+    with(|ext| {
+        ext.storage.insert(*key, *value);
+    })
+}
+
+pub fn sender() -> Address {
+    with(|ext| ext.sender)
+}
+
+pub fn value() -> U256 {
+    with(|ext| ext.value)
+}
+
+pub fn address() -> Address {
+    with(|ext| ext.address)
+}
+
+pub fn balance(address: &Address) -> U256 {
+    with(|ext| ext.balances.get(address).cloned().unwrap_or_default())
+}
+
+pub fn extcodesize(address: &Address) -> usize {
+    with(|ext| ext.code_sizes.get(address).cloned().unwrap_or(0))
+}
+
+pub fn block_number() -> u64 {
+    with(|ext| ext.block_number)
+}
+
+pub fn timestamp() -> u64 {
+    with(|ext| ext.timestamp)
+}
+
+pub fn gas_left() -> u64 {
+    with(|ext| ext.gas_left)
+}
+
+pub fn input() -> Vec<u8> {
+    with(|ext| ext.input.clone())
+}
+
+/// The output of the last call made, or the data it reverted with.
+pub fn return_data() -> Vec<u8> {
+    with(|ext| ext.return_data.clone())
+}
+
+/// Stops execution, returning `data`.  Natively, this unwinds with a
+/// [`Return`] payload, which can be caught with `std::panic::catch_unwind`.
+pub fn ret(data: &[u8]) -> ! {
+    panic::resume_unwind(Box::new(Return(data.to_vec())))
+}
+
+/// Stops execution and reverts, with `payload` as the revert data.
+/// Natively, this unwinds with a [`Panic`] payload.
+pub fn panic(payload: &[u8]) -> ! {
+    panic::resume_unwind(Box::new(Panic(payload.to_vec())))
+}
+
+/// Runs `f`, returning the data it reverts with if it calls [`panic`].  Any
+/// other panic is propagated.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn catch_revert<R, F: FnOnce() -> R>(f: F) -> Result<R, Vec<u8>> {
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).map_err(|payload| {
+        match payload.downcast::<Panic>() {
+            Ok(data) => data.0,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    })
+}
+
+pub fn call(
+    gas: u64,
+    address: &Address,
+    value: U256,
+    input: &[u8],
+    result: &mut [u8],
+) -> Result<(), Error> {
+    dispatch_call(CallKind::Call, gas, *address, value, input, result)
+}
+
+pub fn call_code(
+    gas: u64,
+    address: &Address,
+    input: &[u8],
+    result: &mut [u8],
+) -> Result<(), Error> {
+    dispatch_call(
+        CallKind::CallCode,
+        gas,
+        *address,
+        U256::zero(),
+        input,
+        result,
+    )
+}
+
+/// Records a call and runs the stub for its address.  No borrow of the
+/// externalities is held while the stub runs, so that it may use them itself.
+fn dispatch_call(
+    kind: CallKind,
+    gas: u64,
+    address: Address,
+    value: U256,
+    input: &[u8],
+    result: &mut [u8],
+) -> Result<(), Error> {
+    let record = CallRecord {
+        kind,
+        gas,
+        address,
+        value,
+        input: input.to_vec(),
+    };
+    let stub = with(|ext| {
+        ext.calls.push(record.clone());
+        ext.stubs.get(&address).or(ext.handler.as_ref()).cloned()
+    });
+    let output = match stub {
+        Some(stub) => stub(&record),
+        None => Ok(Vec::new()),
+    };
+    let data = match &output {
+        Ok(data) | Err(data) => data.clone(),
+    };
+    with(|ext| ext.return_data = data);
+    let output = output.map_err(|_| Error)?;
+    for (i, byte) in result.iter_mut().enumerate() {
+        *byte = output.get(i).cloned().unwrap_or(0);
+    }
+    Ok(())
+}
+
+/// The `pwasm_ethereum` import that emits a log.
+#[no_mangle]
+pub unsafe extern "C" fn elog(
+    topic_ptr: *const u8,
+    topic_count: u32,
+    data_ptr: *const u8,
+    data_len: u32,
+) {
+    let topics = slice::from_raw_parts(topic_ptr, topic_count as usize * 32)
+        .chunks(32)
+        .map(H256::from_slice)
+        .collect();
+    let data = slice::from_raw_parts(data_ptr, data_len as usize).to_vec();
+    with(|ext| {
+        let address = ext.address;
+        ext.logs.push(LogEntry {
+            address,
+            topics,
+            data,
+        })
+    })
+}
+
+/// The `pwasm_ethereum` import that makes a message call.  Returns zero on
+/// success.
+#[no_mangle]
+pub unsafe extern "C" fn ccall(
+    gas: i64,
+    address: *const u8,
+    val_ptr: *const u8,
+    input_ptr: *const u8,
+    input_len: u32,
+    result_ptr: *mut u8,
+    result_len: u32,
+) -> i32 {
+    let address = Address::from_slice(slice::from_raw_parts(address, 20));
+    let value = U256::from_big_endian(slice::from_raw_parts(val_ptr, 32));
+    let input = slice::from_raw_parts(input_ptr, input_len as usize);
+    let result = slice::from_raw_parts_mut(result_ptr, result_len as usize);
+    match call(gas as u64, &address, value, input, result) {
+        Ok(()) => 0,
+        Err(Error) => 1,
+    }
+}
@@ -17,50 +17,65 @@
 #![no_std]
 #![feature(alloc)]
 extern crate alloc;
+#[cfg(not(target_arch = "wasm32"))]
+extern crate std;
 
-use pwasm_abi_derive::eth_abi;
 use pwasm_ethereum;
 
-use pwasm_std::{
-    types::{Address, U256},
-    Vec,
-};
+/// Fails the build unless exactly one of the contract features is enabled,
+/// as each of them exports its own `call` and `deploy`.  Native builds, which
+/// export nothing, may enable none.
+macro_rules! contract_features {
+    ($($feature:tt),*) => {
+        contract_features!(@pairs $($feature),*);
+        #[cfg(all(target_arch = "wasm32", not(any($(feature = $feature),*))))]
+        compile_error!("No contract feature is enabled: enable exactly one");
+    };
+    (@pairs) => {};
+    (@pairs $first:tt $(, $rest:tt)*) => {
+        $(
+            #[cfg(all(feature = $first, feature = $rest))]
+            compile_error!(concat!(
+                "Features `", $first, "` and `", $rest,
+                "` are both enabled: enable exactly one contract feature"
+            ));
+        )*
+        contract_features!(@pairs $($rest),*);
+    };
+}
+
+contract_features!(
+    "bridge-validators",
+    "proxy",
+    "erc677-token",
+    "home-bridge-native-to-erc",
+    "foreign-bridge-native-to-erc",
+    "home-bridge-erc-to-erc",
+    "foreign-bridge-erc-to-erc",
+    "home-bridge-erc-to-native",
+    "multisig-wallet"
+);
+
 mod block_reward;
 mod bridge_validators;
+mod dispatch;
+mod erc677_bridge_token;
+mod error;
+mod ext;
+mod multisig_wallet;
 mod parse_message;
+#[cfg(test)]
+mod simulator;
 mod upgradeability;
 mod upgradeable_contracts;
-use self::upgradeability::upgradeability_storage::{create_storage_input, UpgradeabilityStorage};
-// Declares the dispatch and dispatch_ctor methods
-use pwasm_abi::eth::EndpointInterface;
-
-#[eth_abi(BurnableMintableERC677Token)]
-trait IBurnableMintableERC677Token: IERC677 {
-    fn mint(&self, _: Address, _: U256) -> bool;
-    fn burn(&self, _value: U256);
-    fn claimTokens(&self, _token: Address, _to: Address);
-}
-
-#[eth_abi(ERC677)]
-trait IERC677: ERC20 {
-    #[event]
-    fn Transfer(indexed_from: Address, indexed_to: Address, value: U256, data: Vec<u8>);
-    fn transferAndCall(&self, _: Address, _: U256, _: Vec<u8>) -> bool;
-}
-#[cfg(none)]
+#[cfg(feature = "proxy")]
 #[no_mangle]
 pub fn call() {
-    let mut endpoint =
-        UpgradeabilityStorage::new(create_storage_input(U256::zero(), Address::zero()));
-    // Read http://solidity.readthedocs.io/en/develop/abi-spec.html#formal-specification-of-the-encoding for details
-    pwasm_ethereum::ret(&endpoint.dispatch(&pwasm_ethereum::input()));
+    ext::ret(&upgradeability::run(&ext::input()));
 }
 
-#[cfg(none)]
+#[cfg(feature = "proxy")]
 #[no_mangle]
 pub fn deploy() {
-    let mut endpoint =
-        UpgradeabilityStorage::new(create_storage_input(U256::zero(), Address::zero()));
-    //
-    endpoint.dispatch_ctor(&pwasm_ethereum::input());
+    upgradeability::construct(&ext::input());
 }
@@ -0,0 +1,832 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+//! A multisignature wallet, modelled on the Gnosis `MultiSigWallet`.
+//!
+//! The wallet is meant to administer the bridge: make it the owner of
+//! `BridgeValidators` and of `OwnedUpgradeabilityProxy`, and grant it the
+//! admin role, so that no single key can change the validators or upgrade
+//! the bridge.  Any owner can submit a transaction; it is executed once
+//! `required` owners have confirmed it.
+//!
+//! Owners and the threshold can only be changed by the wallet itself, i.e.
+//! by a transaction that the owners have confirmed.
+use crate::alloc::collections::BTreeSet;
+use crate::error::{require, BridgeError};
+use crate::ext::{address, call, gas_left, sender, value};
+use crate::upgradeability::eternal_storage::{key, EternalStorage};
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::{
+    types::{Address, H256, U256},
+    Vec,
+};
+
+/// The most owners a wallet may have, so that looping over them cannot run
+/// out of gas.
+const MAX_OWNER_COUNT: u64 = 50;
+
+#[eth_abi(MultiSigWallet)]
+pub trait MultiSigWalletInterface {
+    #[event]
+    fn Confirmation(&mut self, indexed_sender: Address, indexed_transactionId: U256);
+
+    #[event]
+    fn Revocation(&mut self, indexed_sender: Address, indexed_transactionId: U256);
+
+    #[event]
+    fn Submission(&mut self, indexed_transactionId: U256);
+
+    #[event]
+    fn Execution(&mut self, indexed_transactionId: U256);
+
+    #[event]
+    fn ExecutionFailure(&mut self, indexed_transactionId: U256);
+
+    #[event]
+    fn Deposit(&mut self, indexed_sender: Address, value: U256);
+
+    #[event]
+    fn OwnerAddition(&mut self, indexed_owner: Address);
+
+    #[event]
+    fn OwnerRemoval(&mut self, indexed_owner: Address);
+
+    #[event]
+    fn RequirementChange(&mut self, required: U256);
+
+    /// Creates a wallet owned by `owners` that requires `required`
+    /// confirmations per transaction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `owners` contains the zero address or a duplicate, or if
+    /// `required` is zero or greater than the number of owners.
+    fn constructor(&mut self, owners: Vec<Address>, required: U256);
+
+    /// Adds `owner`.  Can only be called by the wallet itself.
+    fn addOwner(&mut self, owner: Address);
+
+    /// Removes `owner`, lowering `required` if there would otherwise be too
+    /// few owners to meet it.  Can only be called by the wallet itself.
+    fn removeOwner(&mut self, owner: Address);
+
+    /// Changes the number of confirmations required.  Can only be called by
+    /// the wallet itself.
+    fn changeRequirement(&mut self, required: U256);
+
+    /// Submits a call of `destination` with `value` and `data`, and confirms
+    /// it on behalf of the sender.  Returns the ID of the transaction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an owner, or if `destination` is the
+    /// zero address.
+    fn submitTransaction(&mut self, destination: Address, value: U256, data: Vec<u8>) -> U256;
+
+    /// Confirms transaction `transactionId`, and executes it if it now has
+    /// enough confirmations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an owner, if the transaction does not
+    /// exist, or if `msg.sender` has already confirmed it.
+    fn confirmTransaction(&mut self, transactionId: U256);
+
+    /// Withdraws the sender's confirmation of transaction `transactionId`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an owner, if it has not confirmed the
+    /// transaction, or if the transaction has already been executed.
+    fn revokeConfirmation(&mut self, transactionId: U256);
+
+    /// Executes transaction `transactionId` if it has enough confirmations.
+    /// If the call fails, `ExecutionFailure` is fired and the transaction can
+    /// be executed again later.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an owner or has not confirmed the
+    /// transaction, or if the transaction does not exist or has already been
+    /// executed.
+    fn executeTransaction(&mut self, transactionId: U256);
+
+    #[constant]
+    fn isOwner(&self, owner: Address) -> bool;
+
+    #[constant]
+    fn getOwners(&self) -> Vec<Address>;
+
+    #[constant]
+    fn required(&self) -> U256;
+
+    #[constant]
+    fn transactionCount(&self) -> U256;
+
+    /// Returns the destination, value, and execution status of a transaction.
+    #[constant]
+    fn transactions(&self, transactionId: U256) -> (Address, U256, bool);
+
+    /// Returns the data of a transaction.
+    #[constant]
+    fn transactionData(&self, transactionId: U256) -> Vec<u8>;
+
+    #[constant]
+    fn confirmations(&self, transactionId: U256, owner: Address) -> bool;
+
+    /// Tells whether a transaction has `required` confirmations from
+    /// distinct current owners.
+    #[constant]
+    fn isConfirmed(&self, transactionId: U256) -> bool;
+
+    #[constant]
+    fn getConfirmationCount(&self, transactionId: U256) -> U256;
+
+    /// Returns the current owners that have confirmed a transaction.
+    #[constant]
+    fn getConfirmations(&self, transactionId: U256) -> Vec<Address>;
+}
+
+pub struct MultiSigWalletContract;
+
+impl EternalStorage for MultiSigWalletContract {}
+
+/// The key of entry `index` of a map indexed by a number.
+fn index_key(name: &[u8], index: U256) -> H256 {
+    key(name, &<[u8; 32]>::from(index))
+}
+
+/// The key of the confirmation of transaction `id` by `owner`.
+fn confirmation_key(id: U256, owner: Address) -> H256 {
+    let mut buf = [0; 52];
+    buf[..32].copy_from_slice(&<[u8; 32]>::from(id));
+    buf[32..].copy_from_slice(owner.as_ref());
+    key(b"confirmations", &buf)
+}
+
+fn valid_requirement(owner_count: usize, required: U256) -> bool {
+    owner_count as u64 <= MAX_OWNER_COUNT
+        && !required.is_zero()
+        && required <= U256::from(owner_count)
+}
+
+impl MultiSigWalletContract {
+    /// The payable fallback.  Accepts deposits.
+    pub fn fallback(&mut self) {
+        let value = value();
+        if !value.is_zero() {
+            self.Deposit(sender(), value);
+        }
+    }
+
+    fn onlyWallet(&self) {
+        require(sender() == address(), BridgeError::NotWallet)
+    }
+
+    fn onlyOwner(&self) {
+        require(self.isOwner(sender()), BridgeError::NotWalletOwner)
+    }
+
+    fn transactionExists(&self, transactionId: U256) {
+        require(
+            transactionId < self.transactionCount(),
+            BridgeError::TransactionNotFound,
+        )
+    }
+
+    fn setOwners(&mut self, owners: &[Address]) {
+        let old_count = self.getOwners().len();
+        for i in owners.len()..old_count {
+            self.setAddressStorage(index_key(b"owners", i.into()), Address::zero());
+        }
+        for (i, &owner) in owners.iter().enumerate() {
+            self.setAddressStorage(index_key(b"owners", i.into()), owner);
+        }
+        self.setUintStorage(key(b"ownerCount", &[]), owners.len().into());
+    }
+
+    fn setRequired(&mut self, required: U256) {
+        self.setUintStorage(key(b"required", &[]), required);
+        self.RequirementChange(required);
+    }
+}
+
+impl MultiSigWalletInterface for MultiSigWalletContract {
+    fn constructor(&mut self, owners: Vec<Address>, required: U256) {
+        require(
+            valid_requirement(owners.len(), required),
+            BridgeError::InvalidRequirement,
+        );
+        // Owners are deduplicated as in
+        // `parse_message::has_enough_valid_signatures`, so that a key cannot
+        // count twice towards `required`.
+        let mut encountered_addresses: BTreeSet<Address> = BTreeSet::new();
+        for &owner in &owners {
+            require(owner != Address::zero(), BridgeError::ZeroAddress);
+            require(
+                !encountered_addresses.contains(&owner),
+                BridgeError::DuplicateOwner,
+            );
+            encountered_addresses.insert(owner);
+            self.setBoolStorage(key(b"isOwner", owner.as_ref()), true);
+        }
+        self.setOwners(&owners);
+        self.setRequired(required);
+    }
+
+    fn addOwner(&mut self, owner: Address) {
+        self.onlyWallet();
+        require(owner != Address::zero(), BridgeError::ZeroAddress);
+        require(!self.isOwner(owner), BridgeError::AlreadyOwner);
+        let mut owners = self.getOwners();
+        owners.push(owner);
+        require(
+            valid_requirement(owners.len(), self.required()),
+            BridgeError::InvalidRequirement,
+        );
+        self.setBoolStorage(key(b"isOwner", owner.as_ref()), true);
+        self.setOwners(&owners);
+        self.OwnerAddition(owner);
+    }
+
+    fn removeOwner(&mut self, owner: Address) {
+        self.onlyWallet();
+        require(self.isOwner(owner), BridgeError::UnknownOwner);
+        let owners: Vec<Address> = self
+            .getOwners()
+            .into_iter()
+            .filter(|&o| o != owner)
+            .collect();
+        require(!owners.is_empty(), BridgeError::LastOwner);
+        self.setBoolStorage(key(b"isOwner", owner.as_ref()), false);
+        self.setOwners(&owners);
+        self.OwnerRemoval(owner);
+        if self.required() > owners.len().into() {
+            self.setRequired(owners.len().into());
+        }
+    }
+
+    fn changeRequirement(&mut self, required: U256) {
+        self.onlyWallet();
+        require(
+            valid_requirement(self.getOwners().len(), required),
+            BridgeError::InvalidRequirement,
+        );
+        self.setRequired(required);
+    }
+
+    fn submitTransaction(&mut self, destination: Address, value: U256, data: Vec<u8>) -> U256 {
+        self.onlyOwner();
+        require(destination != Address::zero(), BridgeError::ZeroAddress);
+        let transactionId = self.transactionCount();
+        self.setAddressStorage(index_key(b"destination", transactionId), destination);
+        self.setUintStorage(index_key(b"value", transactionId), value);
+        self.setBytesStorage(index_key(b"data", transactionId), &data);
+        self.setUintStorage(key(b"transactionCount", &[]), transactionId + U256::from(1));
+        self.Submission(transactionId);
+        self.confirmTransaction(transactionId);
+        transactionId
+    }
+
+    fn confirmTransaction(&mut self, transactionId: U256) {
+        self.onlyOwner();
+        self.transactionExists(transactionId);
+        let sender = sender();
+        require(
+            !self.confirmations(transactionId, sender),
+            BridgeError::AlreadyConfirmed,
+        );
+        self.setBoolStorage(confirmation_key(transactionId, sender), true);
+        self.Confirmation(sender, transactionId);
+        if self.isConfirmed(transactionId) && !self.transactions(transactionId).2 {
+            self.executeTransaction(transactionId);
+        }
+    }
+
+    fn revokeConfirmation(&mut self, transactionId: U256) {
+        self.onlyOwner();
+        let sender = sender();
+        require(
+            self.confirmations(transactionId, sender),
+            BridgeError::NotConfirmed,
+        );
+        require(
+            !self.transactions(transactionId).2,
+            BridgeError::AlreadyExecuted,
+        );
+        self.setBoolStorage(confirmation_key(transactionId, sender), false);
+        self.Revocation(sender, transactionId);
+    }
+
+    fn executeTransaction(&mut self, transactionId: U256) {
+        self.onlyOwner();
+        self.transactionExists(transactionId);
+        require(
+            self.confirmations(transactionId, sender()),
+            BridgeError::NotConfirmed,
+        );
+        let (destination, value, executed) = self.transactions(transactionId);
+        require(!executed, BridgeError::AlreadyExecuted);
+        if !self.isConfirmed(transactionId) {
+            return;
+        }
+        // Marked as executed before the call, so that it cannot re-enter.
+        self.setBoolStorage(index_key(b"executed", transactionId), true);
+        let data = self.transactionData(transactionId);
+        if call(gas_left(), &destination, value, &data, &mut []).is_ok() {
+            self.Execution(transactionId);
+        } else {
+            self.setBoolStorage(index_key(b"executed", transactionId), false);
+            self.ExecutionFailure(transactionId);
+        }
+    }
+
+    fn isOwner(&self, owner: Address) -> bool {
+        self.boolStorage(key(b"isOwner", owner.as_ref()))
+    }
+
+    fn getOwners(&self) -> Vec<Address> {
+        let count = self.uintStorage(key(b"ownerCount", &[])).low_u64();
+        (0..count)
+            .map(|i| self.addressStorage(index_key(b"owners", i.into())))
+            .collect()
+    }
+
+    fn required(&self) -> U256 {
+        self.uintStorage(key(b"required", &[]))
+    }
+
+    fn transactionCount(&self) -> U256 {
+        self.uintStorage(key(b"transactionCount", &[]))
+    }
+
+    fn transactions(&self, transactionId: U256) -> (Address, U256, bool) {
+        (
+            self.addressStorage(index_key(b"destination", transactionId)),
+            self.uintStorage(index_key(b"value", transactionId)),
+            self.boolStorage(index_key(b"executed", transactionId)),
+        )
+    }
+
+    fn transactionData(&self, transactionId: U256) -> Vec<u8> {
+        self.bytesStorage(index_key(b"data", transactionId))
+    }
+
+    fn confirmations(&self, transactionId: U256, owner: Address) -> bool {
+        self.boolStorage(confirmation_key(transactionId, owner))
+    }
+
+    fn isConfirmed(&self, transactionId: U256) -> bool {
+        self.getConfirmationCount(transactionId) >= self.required()
+    }
+
+    fn getConfirmationCount(&self, transactionId: U256) -> U256 {
+        self.getConfirmations(transactionId).len().into()
+    }
+
+    fn getConfirmations(&self, transactionId: U256) -> Vec<Address> {
+        // Only confirmations by current owners count, so that removing an
+        // owner also withdraws its confirmations.
+        self.getOwners()
+            .into_iter()
+            .filter(|&owner| self.confirmations(transactionId, owner))
+            .collect()
+    }
+}
+
+/// Runs the wallet on the call data `input`, returning its output.
+#[cfg(any(feature = "multisig-wallet", not(target_arch = "wasm32")))]
+pub fn run(input: &[u8]) -> Vec<u8> {
+    use pwasm_abi::eth::EndpointInterface;
+    if input.is_empty() {
+        MultiSigWalletContract.fallback();
+        Vec::new()
+    } else {
+        MultiSigWallet::new(MultiSigWalletContract).dispatch(input)
+    }
+}
+
+/// Runs the constructor of the wallet on `input`.
+#[cfg(any(feature = "multisig-wallet", not(target_arch = "wasm32")))]
+pub fn construct(input: &[u8]) {
+    use pwasm_abi::eth::EndpointInterface;
+    MultiSigWallet::new(MultiSigWalletContract).dispatch_ctor(input)
+}
+
+#[cfg(feature = "multisig-wallet")]
+#[no_mangle]
+pub fn call() {
+    crate::ext::ret(&run(&crate::ext::input()));
+}
+
+#[cfg(feature = "multisig-wallet")]
+#[no_mangle]
+pub fn deploy() {
+    construct(&crate::ext::input());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::BridgeError;
+    use crate::simulator::abi::{self, Token};
+    use crate::simulator::{Chain, Revert, BRIDGE_VALIDATORS, MULTISIG_WALLET};
+    use crate::upgradeable_contracts::access_control::{
+        DEFAULT_ADMIN_ROLE, UPGRADER_ROLE, VALIDATOR_MANAGER_ROLE,
+    };
+    use pwasm_std::types::{Address, U256};
+    use std::{vec, vec::Vec};
+
+    fn admin() -> Address {
+        Address::from([0xee; 20])
+    }
+
+    fn owner(i: u8) -> Address {
+        Address::from([i; 20])
+    }
+
+    /// A wallet with owners `1..=3`, two of whom must confirm each
+    /// transaction, that owns `BridgeValidators` and its proxy.
+    struct Fixture {
+        chain: Chain,
+        wallet: Address,
+        validators: Address,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let mut chain = Chain::new(1);
+            let wallet = chain
+                .deploy(
+                    admin(),
+                    MULTISIG_WALLET,
+                    &abi::encode(&[
+                        Token::Array((1..=3).map(|i| Token::Address(owner(i))).collect()),
+                        Token::Uint(2.into()),
+                    ]),
+                )
+                .unwrap();
+            let validators = chain
+                .deploy_behind_proxy(admin(), BRIDGE_VALIDATORS)
+                .unwrap();
+            let mut fixture = Fixture {
+                chain,
+                wallet,
+                validators,
+            };
+            fixture.call(
+                admin(),
+                validators,
+                "initialize(uint256,address[],address)",
+                &[
+                    Token::Uint(1.into()),
+                    Token::Array(vec![Token::Address(owner(0x10))]),
+                    Token::Address(admin()),
+                ],
+            );
+            for &(transfer, claim) in &[
+                ("transferOwnership(address)", "claimOwnership()"),
+                ("transferProxyOwnership(address)", "claimProxyOwnership()"),
+            ] {
+                fixture.call(admin(), validators, transfer, &[Token::Address(wallet)]);
+                let id = fixture.submit(1, validators, &abi::encode_call(claim, &[]));
+                fixture.confirm(2, id).unwrap();
+                assert!(fixture.executed(id));
+            }
+            fixture
+        }
+
+        fn call(&mut self, from: Address, to: Address, method: &str, args: &[Token]) -> Vec<u8> {
+            self.chain
+                .transact(from, to, U256::zero(), &abi::encode_call(method, args))
+                .unwrap()
+        }
+
+        fn query(&mut self, to: Address, method: &str, args: &[Token]) -> [u8; 32] {
+            abi::words(&self.call(admin(), to, method, args))[0]
+        }
+
+        /// Submits a call of `destination` with `data` as owner `i`.
+        fn submit(&mut self, i: u8, destination: Address, data: &[u8]) -> U256 {
+            let wallet = self.wallet;
+            abi::decode_uint(
+                &abi::words(&self.call(
+                    owner(i),
+                    wallet,
+                    "submitTransaction(address,uint256,bytes)",
+                    &[
+                        Token::Address(destination),
+                        Token::Uint(0.into()),
+                        Token::Bytes(data.to_vec()),
+                    ],
+                ))[0],
+            )
+        }
+
+        fn wallet_call(&mut self, i: u8, method: &str, id: U256) -> Result<Vec<u8>, Revert> {
+            self.chain.transact(
+                owner(i),
+                self.wallet,
+                U256::zero(),
+                &abi::encode_call(method, &[Token::Uint(id)]),
+            )
+        }
+
+        fn confirm(&mut self, i: u8, id: U256) -> Result<Vec<u8>, Revert> {
+            self.wallet_call(i, "confirmTransaction(uint256)", id)
+        }
+
+        fn executed(&mut self, id: U256) -> bool {
+            let wallet = self.wallet;
+            abi::decode_bool(
+                &abi::words(&self.call(
+                    admin(),
+                    wallet,
+                    "transactions(uint256)",
+                    &[Token::Uint(id)],
+                ))[2],
+            )
+        }
+
+        fn is_validator(&mut self, validator: Address) -> bool {
+            let validators = self.validators;
+            abi::decode_bool(&self.query(
+                validators,
+                "isValidator(address)",
+                &[Token::Address(validator)],
+            ))
+        }
+
+        fn has_role(&mut self, role: [u8; 32], account: Address) -> bool {
+            let validators = self.validators;
+            abi::decode_bool(&self.query(
+                validators,
+                "hasRole(bytes32,address)",
+                &[Token::FixedBytes(role), Token::Address(account)],
+            ))
+        }
+
+        fn add_validator(&mut self, i: u8, validator: Address) -> U256 {
+            let validators = self.validators;
+            self.submit(
+                i,
+                validators,
+                &abi::encode_call("addValidator(address)", &[Token::Address(validator)]),
+            )
+        }
+
+        /// Submits, as owner 1, a call of the wallet itself.
+        fn submit_to_wallet(&mut self, method: &str, args: &[Token]) -> U256 {
+            let wallet = self.wallet;
+            self.submit(1, wallet, &abi::encode_call(method, args))
+        }
+    }
+
+    #[test]
+    fn wallet_takes_over_bridge_validators() {
+        let mut fixture = Fixture::new();
+        let (wallet, validators) = (fixture.wallet, fixture.validators);
+        assert_eq!(
+            abi::decode_address(&fixture.query(validators, "owner()", &[])),
+            wallet
+        );
+        assert_eq!(
+            abi::decode_address(&fixture.query(validators, "proxyOwner()", &[])),
+            wallet
+        );
+        for &role in &[DEFAULT_ADMIN_ROLE, VALIDATOR_MANAGER_ROLE, UPGRADER_ROLE] {
+            assert!(fixture.has_role(role, wallet));
+            assert!(!fixture.has_role(role, admin()));
+        }
+    }
+
+    #[test]
+    fn executes_add_validator_once_confirmed() {
+        let mut fixture = Fixture::new();
+        let new_validator = owner(0x11);
+        let id = fixture.add_validator(1, new_validator);
+        assert!(!fixture.executed(id));
+        assert!(!fixture.is_validator(new_validator));
+        fixture.confirm(3, id).unwrap();
+        assert!(fixture.executed(id));
+        assert!(fixture.is_validator(new_validator));
+        assert_eq!(
+            fixture
+                .wallet_call(3, "executeTransaction(uint256)", id)
+                .unwrap_err()
+                .error,
+            Some(BridgeError::AlreadyExecuted)
+        );
+    }
+
+    #[test]
+    fn executes_upgrade_through_proxy() {
+        let mut fixture = Fixture::new();
+        let validators = fixture.validators;
+        let implementation = fixture
+            .chain
+            .deploy(admin(), BRIDGE_VALIDATORS, &[])
+            .unwrap();
+        let id = fixture.submit(
+            2,
+            validators,
+            &abi::encode_call(
+                "upgradeTo(uint256,address)",
+                &[Token::Uint(2.into()), Token::Address(implementation)],
+            ),
+        );
+        fixture.confirm(1, id).unwrap();
+        assert!(fixture.executed(id));
+        assert_eq!(
+            abi::decode_uint(&fixture.query(validators, "version()", &[])),
+            2.into()
+        );
+        assert_eq!(
+            abi::decode_address(&fixture.query(validators, "implementation()", &[])),
+            implementation
+        );
+        // The validators survive the upgrade.
+        assert!(fixture.is_validator(owner(0x10)));
+    }
+
+    #[test]
+    fn only_wallet_changes_threshold() {
+        let mut fixture = Fixture::new();
+        let wallet = fixture.wallet;
+        assert_eq!(
+            fixture
+                .chain
+                .transact(
+                    owner(1),
+                    wallet,
+                    U256::zero(),
+                    &abi::encode_call("changeRequirement(uint256)", &[Token::Uint(3.into())]),
+                )
+                .unwrap_err()
+                .error,
+            Some(BridgeError::NotWallet)
+        );
+        let id = fixture.submit_to_wallet("changeRequirement(uint256)", &[Token::Uint(3.into())]);
+        fixture.confirm(2, id).unwrap();
+        assert_eq!(
+            abi::decode_uint(&fixture.query(wallet, "required()", &[])),
+            3.into()
+        );
+
+        // Two confirmations are no longer enough.
+        let new_validator = owner(0x11);
+        let id = fixture.add_validator(1, new_validator);
+        fixture.confirm(2, id).unwrap();
+        assert!(!fixture.executed(id));
+        fixture.confirm(3, id).unwrap();
+        assert!(fixture.is_validator(new_validator));
+
+        // A threshold above the number of owners fails to execute.
+        let id = fixture.submit_to_wallet("changeRequirement(uint256)", &[Token::Uint(4.into())]);
+        fixture.confirm(2, id).unwrap();
+        fixture.confirm(3, id).unwrap();
+        assert!(!fixture.executed(id));
+        assert_eq!(
+            abi::decode_uint(&fixture.query(wallet, "required()", &[])),
+            3.into()
+        );
+    }
+
+    #[test]
+    fn revoked_confirmation_does_not_count() {
+        let mut fixture = Fixture::new();
+        let new_validator = owner(0x11);
+        let id = fixture.add_validator(1, new_validator);
+        fixture
+            .wallet_call(1, "revokeConfirmation(uint256)", id)
+            .unwrap();
+        assert_eq!(
+            fixture
+                .wallet_call(1, "revokeConfirmation(uint256)", id)
+                .unwrap_err()
+                .error,
+            Some(BridgeError::NotConfirmed)
+        );
+        fixture.confirm(2, id).unwrap();
+        assert!(!fixture.executed(id));
+        assert!(!fixture.is_validator(new_validator));
+        fixture.confirm(3, id).unwrap();
+        assert!(fixture.is_validator(new_validator));
+        assert_eq!(
+            fixture
+                .wallet_call(2, "revokeConfirmation(uint256)", id)
+                .unwrap_err()
+                .error,
+            Some(BridgeError::AlreadyExecuted)
+        );
+    }
+
+    #[test]
+    fn removed_owner_loses_confirmations() {
+        let mut fixture = Fixture::new();
+        let wallet = fixture.wallet;
+        let new_validator = owner(0x11);
+        let pending = fixture.add_validator(3, new_validator);
+        let id = fixture.submit_to_wallet("removeOwner(address)", &[Token::Address(owner(3))]);
+        fixture.confirm(2, id).unwrap();
+        assert!(!abi::decode_bool(&fixture.query(
+            wallet,
+            "isOwner(address)",
+            &[Token::Address(owner(3))],
+        )));
+        assert_eq!(
+            abi::decode_uint(&fixture.query(
+                wallet,
+                "getConfirmationCount(uint256)",
+                &[Token::Uint(pending)],
+            )),
+            0.into()
+        );
+        assert_eq!(
+            fixture.confirm(3, pending).unwrap_err().error,
+            Some(BridgeError::NotWalletOwner)
+        );
+        fixture.confirm(1, pending).unwrap();
+        assert!(!fixture.executed(pending));
+
+        // Removing another owner lowers the threshold to the one left.
+        let id = fixture.submit_to_wallet("removeOwner(address)", &[Token::Address(owner(2))]);
+        fixture.confirm(2, id).unwrap();
+        assert_eq!(
+            abi::decode_uint(&fixture.query(wallet, "required()", &[])),
+            1.into()
+        );
+        // Owner 3 confirmed before it was removed, but may not execute.
+        assert_eq!(
+            fixture
+                .wallet_call(3, "executeTransaction(uint256)", pending)
+                .unwrap_err()
+                .error,
+            Some(BridgeError::NotWalletOwner)
+        );
+        assert!(!fixture.executed(pending));
+        assert_eq!(
+            fixture
+                .wallet_call(1, "executeTransaction(uint256)", 99.into())
+                .unwrap_err()
+                .error,
+            Some(BridgeError::TransactionNotFound)
+        );
+        fixture
+            .wallet_call(1, "executeTransaction(uint256)", pending)
+            .unwrap();
+        assert!(fixture.is_validator(new_validator));
+    }
+
+    #[test]
+    fn failed_execution_can_be_retried() {
+        let mut fixture = Fixture::new();
+        let (wallet, validators) = (fixture.wallet, fixture.validators);
+        let id = fixture.submit(
+            1,
+            validators,
+            &abi::encode_call("setRequiredSignatures(uint256)", &[Token::Uint(2.into())]),
+        );
+        fixture.confirm(2, id).unwrap();
+        assert!(!fixture.executed(id));
+        assert_eq!(
+            fixture
+                .chain
+                .events(wallet, "ExecutionFailure(uint256)")
+                .len(),
+            1
+        );
+
+        // With a second validator, the call succeeds.
+        let add = fixture.add_validator(1, owner(0x11));
+        fixture.confirm(2, add).unwrap();
+        fixture
+            .wallet_call(1, "executeTransaction(uint256)", id)
+            .unwrap();
+        assert!(fixture.executed(id));
+        assert_eq!(
+            abi::decode_uint(&fixture.query(validators, "requiredSignatures()", &[])),
+            2.into()
+        );
+        assert_eq!(
+            fixture
+                .wallet_call(1, "executeTransaction(uint256)", id)
+                .unwrap_err()
+                .error,
+            Some(BridgeError::AlreadyExecuted)
+        );
+    }
+}
@@ -18,23 +18,23 @@ pub const REQUIRED_MESSAGE_LENGTH: usize = 104;
 pub const SIGNATURE_LENGTH: usize = 65;
 use super::bridge_validators::IBridgeValidators;
 use crate::alloc::collections::BTreeSet;
+use crate::error::{require, revert, BridgeError};
 use pwasm_std::{
     keccak,
     types::{Address, H256, U256},
     Vec,
 };
 pub struct ParsedMessage {
-    recipient: Address,
-    amount: U256,
-    tx_hash: H256,
-    contract_address: Address,
+    pub recipient: Address,
+    pub amount: U256,
+    pub tx_hash: H256,
+    pub contract_address: Address,
 }
 
 pub fn parse_message(message: &[u8]) -> ParsedMessage {
-    assert_eq!(
-        message.len(),
-        REQUIRED_MESSAGE_LENGTH,
-        "Invalid message length (must be 104 bytes)"
+    require(
+        message.len() == REQUIRED_MESSAGE_LENGTH,
+        BridgeError::InvalidMessageLength,
     );
     ParsedMessage {
         recipient: Address::from_slice(&message[..20]),
@@ -44,17 +44,115 @@ pub fn parse_message(message: &[u8]) -> ParsedMessage {
     }
 }
 
-fn ecrecover(_hash: H256, _byte: u8, _h1: H256, _h2: H256) -> Address {
-    unimplemented!()
+/// The order of the secp256k1 curve.  `r` must be in the range
+/// `1..SECP256K1_N`.
+const SECP256K1_N: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Half the order of the secp256k1 curve, rounded down.  `s` must be in the
+/// range `1..=SECP256K1_HALF_N`, which makes signatures non-malleable: for
+/// every signature `(v, r, s)` there is another, `(v ^ 1, r, n - s)`, that
+/// recovers the same address.
+const SECP256K1_HALF_N: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Address of the `ecrecover` precompiled contract.
+#[cfg(target_arch = "wasm32")]
+const ECRECOVER_PRECOMPILE: [u8; 20] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+
+/// Gas passed to the `ecrecover` precompile.  It costs 3000 gas.
+#[cfg(target_arch = "wasm32")]
+const ECRECOVER_GAS: u64 = 3000;
+
+/// Recovers the address that signed `hash`, given the `v`, `r`, and `s`
+/// components of the signature.
+///
+/// Returns `None` if `v` is not 27 or 28, if `r` is not in the range `1..n`
+/// (where `n` is the order of the curve), if `s` is not in the range
+/// `1..=n / 2`, or if no public key can be recovered from the signature.
+pub fn ecrecover(hash: H256, v: u8, r: H256, s: H256) -> Option<Address> {
+    if v != 27 && v != 28 {
+        return None;
+    }
+    let (r_value, s_value) = (
+        U256::from(<[u8; 32]>::from(r)),
+        U256::from(<[u8; 32]>::from(s)),
+    );
+    if r_value.is_zero() || r_value >= U256::from(SECP256K1_N) {
+        return None;
+    }
+    if s_value.is_zero() || s_value > U256::from(SECP256K1_HALF_N) {
+        return None;
+    }
+    recover_public_key(hash, v, r, s)
 }
 
-pub fn recover_address_from_signed_message(signature: &[u8], message: &[u8]) -> Address {
-    assert_eq!(
-        message.len(),
-        REQUIRED_MESSAGE_LENGTH,
-        "Invalid message length (must be 104 bytes)"
+/// Calls the `ecrecover` precompile.  The precompile returns no data if
+/// recovery fails, leaving the output buffer zeroed.
+#[cfg(target_arch = "wasm32")]
+fn recover_public_key(hash: H256, v: u8, r: H256, s: H256) -> Option<Address> {
+    let mut input = [0; 128];
+    input[..32].copy_from_slice(&hash[..]);
+    input[63] = v;
+    input[64..96].copy_from_slice(&r[..]);
+    input[96..].copy_from_slice(&s[..]);
+    let mut output = [0; 32];
+    crate::ext::call(
+        ECRECOVER_GAS,
+        &Address::from(ECRECOVER_PRECOMPILE),
+        U256::zero(),
+        &input,
+        &mut output,
+    )
+    .ok()?;
+    let address = Address::from_slice(&output[12..]);
+    if address.is_zero() {
+        None
+    } else {
+        Some(address)
+    }
+}
+
+/// Pure-Rust public key recovery, used when not running inside the wasm
+/// host.
+#[cfg(not(target_arch = "wasm32"))]
+fn recover_public_key(hash: H256, v: u8, r: H256, s: H256) -> Option<Address> {
+    use secp256k1::{recover, Message, RecoveryId, Signature};
+    let mut signature = [0; 64];
+    signature[..32].copy_from_slice(&r[..]);
+    signature[32..].copy_from_slice(&s[..]);
+    let public_key = recover(
+        &Message::parse(&hash.into()),
+        &Signature::parse(&signature),
+        &RecoveryId::parse(v - 27).ok()?,
+    )
+    .ok()?;
+    // The first byte is the uncompressed point tag (0x04).
+    let hashed_key = keccak(&public_key.serialize()[1..]);
+    Some(Address::from_slice(&hashed_key[12..]))
+}
+
+/// Recovers the address that signed `message`.  `signature` is `r`, `s` and
+/// `v`, concatenated.
+///
+/// Returns `None` if the signature is not valid.
+///
+/// # Panics
+///
+/// Panics if `message` or `signature` do not have the correct length.
+pub fn recover_address_from_signed_message(signature: &[u8], message: &[u8]) -> Option<Address> {
+    require(
+        message.len() == REQUIRED_MESSAGE_LENGTH,
+        BridgeError::InvalidMessageLength,
+    );
+    require(
+        signature.len() == SIGNATURE_LENGTH,
+        BridgeError::InvalidSignatureLength,
     );
-    assert_eq!(signature.len(), 65);
     ecrecover(
         hash_message(message),
         signature[64],
@@ -63,40 +161,127 @@ pub fn recover_address_from_signed_message(signature: &[u8], message: &[u8]) ->
     )
 }
 
-fn has_enough_valid_signatures(
+pub fn has_enough_valid_signatures(
     message: &[u8],
     vs: &[u8],
     rs: &[H256],
     ss: &[H256],
     validator_contract: &dyn IBridgeValidators,
 ) {
-    assert_eq!(
-        message.len(),
-        REQUIRED_MESSAGE_LENGTH,
-        "Invalid message length (must be 104 bytes)"
+    require(
+        message.len() == REQUIRED_MESSAGE_LENGTH,
+        BridgeError::InvalidMessageLength,
     );
     let required_signatures = validator_contract.required_signatures();
-    assert!(vs.len() >= required_signatures, "Not enough signatures");
+    require(
+        vs.len() >= required_signatures
+            && rs.len() >= required_signatures
+            && ss.len() >= required_signatures,
+        BridgeError::NotEnoughSignatures,
+    );
     let hash = hash_message(message);
     let mut encountered_addresses: BTreeSet<Address> = BTreeSet::new();
     for i in 0..required_signatures {
-        let recovered_address = ecrecover(hash, vs[i], rs[i], ss[i]);
-        assert!(
+        let recovered_address = ecrecover(hash, vs[i], rs[i], ss[i])
+            .unwrap_or_else(|| revert(BridgeError::InvalidSignature));
+        require(
             validator_contract.is_validator(recovered_address),
-            "Signature from non-validator"
+            BridgeError::SignatureFromNonValidator,
         );
-        assert!(
+        require(
             !encountered_addresses.contains(&recovered_address),
-            "Duplicate signature"
+            BridgeError::DuplicateSignature,
         );
         encountered_addresses.insert(recovered_address);
     }
 }
 
-fn hash_message(message: &[u8]) -> H256 {
+/// Hashes `message` the way `eth_sign` does before signing it.
+pub fn hash_message(message: &[u8]) -> H256 {
     const PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n104";
     let mut v = Vec::with_capacity(PREFIX.len() + message.len());
     v.extend_from_slice(PREFIX);
     v.extend_from_slice(message);
     keccak(&v)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::bridge::Validator;
+
+    fn h256(hex: &str) -> H256 {
+        let mut bytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes.into()
+    }
+
+    /// A signature from the Ethereum test suite for the `ecrecover`
+    /// precompile.
+    fn known_signature() -> (H256, u8, H256, H256) {
+        (
+            h256("456e9aea5e197a1f1af7a3e85a3212fa4049a3ba34c2289b4c860fc0b0c64ef3"),
+            28,
+            h256("9242685bf161793cc25603c231bc2f568eb630ea16aa137d2664ac8038825608"),
+            h256("4f8ae3bd7535248d0bd448298cc2e2071e56992d0774dc340c368ae950852ada"),
+        )
+    }
+
+    #[test]
+    fn recovers_known_signer() {
+        let (hash, v, r, s) = known_signature();
+        let expected = Address::from_slice(
+            &h256("0000000000000000000000007156526fbd7a3c72969b54f64e42c10fbb768c8a")[12..],
+        );
+        assert_eq!(ecrecover(hash, v, r, s), Some(expected));
+    }
+
+    #[test]
+    fn rejects_bad_v() {
+        let (hash, _, r, s) = known_signature();
+        for &v in &[0, 1, 26, 29, 255] {
+            assert_eq!(ecrecover(hash, v, r, s), None);
+        }
+    }
+
+    #[test]
+    fn rejects_zero_r_and_s() {
+        let (hash, v, r, s) = known_signature();
+        assert_eq!(ecrecover(hash, v, H256::zero(), s), None);
+        assert_eq!(ecrecover(hash, v, r, H256::zero()), None);
+    }
+
+    #[test]
+    fn rejects_r_not_below_curve_order() {
+        let (hash, v, _, s) = known_signature();
+        assert_eq!(ecrecover(hash, v, SECP256K1_N.into(), s), None);
+    }
+
+    #[test]
+    fn rejects_high_s() {
+        let (hash, v, r, s) = known_signature();
+        let high_s = U256::from(SECP256K1_N) - U256::from(<[u8; 32]>::from(s));
+        let high_s: [u8; 32] = high_s.into();
+        // `(v ^ 1, r, n - s)` recovers the same signer as `(v, r, s)`.
+        assert_eq!(ecrecover(hash, v ^ 1, r, high_s.into()), None);
+    }
+
+    #[test]
+    fn recovers_signer_of_message() {
+        let validator = Validator::new(1);
+        let message = [7; REQUIRED_MESSAGE_LENGTH];
+        let signature = validator.sign(&message);
+        assert_eq!(
+            recover_address_from_signed_message(&signature, &message),
+            Some(validator.address)
+        );
+        let mut forged = signature.clone();
+        forged[64] ^= 1;
+        assert_ne!(
+            recover_address_from_signed_message(&forged, &message),
+            Some(validator.address)
+        );
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Just enough of the Solidity ABI to call the contracts and read their
+//! events from the simulator.
+//!
+//! Read http://solidity.readthedocs.io/en/develop/abi-spec.html#formal-specification-of-the-encoding for details
+use crate::dispatch::method_id;
+use pwasm_std::types::{Address, H256, U256};
+use std::vec::Vec;
+
+/// A value to be encoded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Address(Address),
+    Uint(U256),
+    /// `bytes32`.
+    FixedBytes([u8; 32]),
+    /// `bytes`.
+    Bytes(Vec<u8>),
+    /// A dynamic array, such as `address[]`.
+    Array(Vec<Token>),
+}
+
+impl Token {
+    fn is_dynamic(&self) -> bool {
+        match self {
+            Token::Bytes(_) | Token::Array(_) => true,
+            _ => false,
+        }
+    }
+}
+
+fn word(value: U256) -> [u8; 32] {
+    value.into()
+}
+
+/// Encodes `tokens` as a tuple.
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let mut head = Vec::with_capacity(32 * tokens.len());
+    let mut tail = Vec::new();
+    for token in tokens {
+        if token.is_dynamic() {
+            head.extend_from_slice(&word((32 * tokens.len() + tail.len()).into()));
+            encode_token(token, &mut tail);
+        } else {
+            encode_token(token, &mut head);
+        }
+    }
+    head.extend(tail);
+    head
+}
+
+fn encode_token(token: &Token, out: &mut Vec<u8>) {
+    match token {
+        Token::Address(address) => out.extend_from_slice(&H256::from(*address)[..]),
+        Token::Uint(value) => out.extend_from_slice(&word(*value)),
+        Token::FixedBytes(bytes) => out.extend_from_slice(bytes),
+        Token::Bytes(bytes) => {
+            out.extend_from_slice(&word(bytes.len().into()));
+            out.extend_from_slice(bytes);
+            let padding = (32 - bytes.len() % 32) % 32;
+            out.extend((0..padding).map(|_| 0));
+        }
+        Token::Array(tokens) => {
+            out.extend_from_slice(&word(tokens.len().into()));
+            out.extend(encode(tokens));
+        }
+    }
+}
+
+/// Encodes a call to the method with the canonical signature `signature`.
+pub fn encode_call(signature: &str, tokens: &[Token]) -> Vec<u8> {
+    let mut input = method_id(signature).to_vec();
+    input.extend(encode(tokens));
+    input
+}
+
+/// Splits `data` into 32-byte words.  Events and the methods the simulator
+/// reads only have static parameters, each of which takes one word.
+pub fn words(data: &[u8]) -> Vec<[u8; 32]> {
+    data.chunks(32)
+        .map(|chunk| {
+            let mut word = [0; 32];
+            word[..chunk.len()].copy_from_slice(chunk);
+            word
+        })
+        .collect()
+}
+
+pub fn decode_address(word: &[u8; 32]) -> Address {
+    Address::from_slice(&word[12..])
+}
+
+pub fn decode_uint(word: &[u8; 32]) -> U256 {
+    U256::from_big_endian(&word[..])
+}
+
+pub fn decode_bool(word: &[u8; 32]) -> bool {
+    word[31] != 0
+}
+
+/// Decodes the output of a method that returns only `bytes`.
+pub fn decode_bytes(output: &[u8]) -> Vec<u8> {
+    let offset = decode_uint(&words(output)[0]).low_u64() as usize;
+    let length = U256::from_big_endian(&output[offset..offset + 32]).low_u64() as usize;
+    output[offset + 32..offset + 32 + length].to_vec()
+}
@@ -0,0 +1,677 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! A native-to-ERC20 bridge between two simulated chains, with validators
+//! that sign and relay transfers the way the bridge oracles do.
+//!
+//! Coins sent to the home bridge are locked, and the validators sign the
+//! transfer with `submitSignature`.  Once enough have signed, the collected
+//! signatures are relayed to the foreign bridge with `executeSignatures`,
+//! which mints the tokens.  Tokens sent back to the foreign bridge with
+//! `transferAndCall` are burnt, and the validators confirm the transfer on
+//! the home chain with `executeAffirmation`, which releases the coins.
+use super::abi::{self, Token};
+use super::{
+    event_topic, Chain, Revert, BRIDGE_VALIDATORS, ERC677_TOKEN, FOREIGN_BRIDGE_NATIVE_TO_ERC,
+    HOME_BRIDGE_NATIVE_TO_ERC,
+};
+use crate::parse_message::{hash_message, REQUIRED_MESSAGE_LENGTH};
+use pwasm_std::keccak;
+use pwasm_std::types::{Address, H256, U256};
+use secp256k1::{sign, Message, PublicKey, SecretKey};
+use std::vec::Vec;
+
+/// The id of the home chain, which picks the addresses of its contracts.
+pub const HOME_CHAIN: u8 = 1;
+
+/// The id of the foreign chain.
+pub const FOREIGN_CHAIN: u8 = 2;
+
+/// A bridge validator, which holds its own signing key.
+pub struct Validator {
+    pub address: Address,
+    secret: SecretKey,
+}
+
+impl Validator {
+    /// Creates the validator with the key derived from `seed`.
+    pub fn new(seed: u8) -> Self {
+        let secret = SecretKey::parse(&keccak(&[b'v', seed]).into()).expect("Invalid secret key");
+        // The first byte is the uncompressed point tag (0x04).
+        let public_key = PublicKey::from_secret_key(&secret).serialize();
+        let address = Address::from_slice(&keccak(&public_key[1..])[12..]);
+        Validator { address, secret }
+    }
+
+    /// Signs `message` the way `eth_sign` does.  Returns `r`, `s`, and `v`,
+    /// concatenated.
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        let hash = hash_message(message);
+        let (signature, recovery_id) =
+            sign(&Message::parse(&hash.into()), &self.secret).expect("Signing failed");
+        let mut result = signature.serialize().to_vec();
+        result.push(27 + recovery_id.serialize());
+        result
+    }
+}
+
+/// The limits set on the home bridge when it is deployed.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    pub daily_limit: U256,
+    pub max_per_tx: U256,
+    pub min_per_tx: U256,
+    pub execution_daily_limit: U256,
+    pub execution_max_per_tx: U256,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        let ether = U256::from(10).pow(18.into());
+        Limits {
+            daily_limit: ether * U256::from(1000),
+            max_per_tx: ether * U256::from(100),
+            min_per_tx: ether / U256::from(100),
+            execution_daily_limit: ether * U256::from(1000),
+            execution_max_per_tx: ether * U256::from(100),
+        }
+    }
+}
+
+/// A home and a foreign chain, with a bridge deployed between them.
+pub struct Bridge {
+    pub home: Chain,
+    pub foreign: Chain,
+    /// The owner of every contract, and the holder of every role.
+    pub owner: Address,
+    pub validators: Vec<Validator>,
+    pub required_signatures: usize,
+    /// The proxies of the upgradeable contracts.
+    pub home_validators: Address,
+    pub home_bridge: Address,
+    pub foreign_validators: Address,
+    pub foreign_bridge: Address,
+    pub token: Address,
+    /// How many of the logs of each chain the validators have processed.
+    home_logs_seen: usize,
+    foreign_logs_seen: usize,
+}
+
+impl Bridge {
+    /// Deploys and initializes the validator contract and the bridge on each
+    /// chain, each behind its own proxy, and the token on the foreign chain.
+    /// There are `validators` validators, `required_signatures` of which
+    /// must sign each transfer.
+    pub fn new(validators: u8, required_signatures: usize, limits: Limits) -> Result<Self, Revert> {
+        let owner = Address::from([0xee; 20]);
+        let validators: Vec<_> = (0..validators).map(Validator::new).collect();
+        let validator_list = Token::Array(
+            validators
+                .iter()
+                .map(|validator| Token::Address(validator.address))
+                .collect(),
+        );
+        let validators_init = abi::encode_call(
+            "initialize(uint256,address[],address)",
+            &[
+                Token::Uint(required_signatures.into()),
+                validator_list,
+                Token::Address(owner),
+            ],
+        );
+
+        let mut home = Chain::new(HOME_CHAIN);
+        let home_validators = home.deploy_behind_proxy(owner, BRIDGE_VALIDATORS)?;
+        home.transact(owner, home_validators, U256::zero(), &validators_init)?;
+        let home_bridge = home.deploy_behind_proxy(owner, HOME_BRIDGE_NATIVE_TO_ERC)?;
+        home.transact(
+            owner,
+            home_bridge,
+            U256::zero(),
+            &abi::encode_call(
+                "initialize(address,address)",
+                &[Token::Address(home_validators), Token::Address(owner)],
+            ),
+        )?;
+        for &(setter, limit) in &[
+            ("setDailyLimit(uint256)", limits.daily_limit),
+            ("setMaxPerTx(uint256)", limits.max_per_tx),
+            ("setMinPerTx(uint256)", limits.min_per_tx),
+            (
+                "setExecutionDailyLimit(uint256)",
+                limits.execution_daily_limit,
+            ),
+            ("setExecutionMaxPerTx(uint256)", limits.execution_max_per_tx),
+        ] {
+            home.transact(
+                owner,
+                home_bridge,
+                U256::zero(),
+                &abi::encode_call(setter, &[Token::Uint(limit)]),
+            )?;
+        }
+
+        let mut foreign = Chain::new(FOREIGN_CHAIN);
+        let foreign_validators = foreign.deploy_behind_proxy(owner, BRIDGE_VALIDATORS)?;
+        foreign.transact(owner, foreign_validators, U256::zero(), &validators_init)?;
+        let token = foreign.deploy(owner, ERC677_TOKEN, &[])?;
+        let foreign_bridge = foreign.deploy_behind_proxy(owner, FOREIGN_BRIDGE_NATIVE_TO_ERC)?;
+        // The bridge claims the token when it is initialized.
+        foreign.transact(
+            owner,
+            token,
+            U256::zero(),
+            &abi::encode_call(
+                "transferOwnership(address)",
+                &[Token::Address(foreign_bridge)],
+            ),
+        )?;
+        foreign.transact(
+            owner,
+            foreign_bridge,
+            U256::zero(),
+            &abi::encode_call(
+                "initialize(address,address,address)",
+                &[
+                    Token::Address(foreign_validators),
+                    Token::Address(token),
+                    Token::Address(owner),
+                ],
+            ),
+        )?;
+
+        let home_logs_seen = home.logs().len();
+        let foreign_logs_seen = foreign.logs().len();
+        Ok(Bridge {
+            home,
+            foreign,
+            owner,
+            validators,
+            required_signatures,
+            home_validators,
+            home_bridge,
+            foreign_validators,
+            foreign_bridge,
+            token,
+            home_logs_seen,
+            foreign_logs_seen,
+        })
+    }
+
+    /// Sends `value` native coins from `user` to the home bridge, requesting
+    /// that the same amount of tokens be minted to `user` on the foreign
+    /// chain.
+    pub fn deposit(&mut self, user: Address, value: U256) -> Result<(), Revert> {
+        self.home.transact(user, self.home_bridge, value, &[])?;
+        Ok(())
+    }
+
+    /// Sends `value` tokens from `user` to the foreign bridge, requesting
+    /// that the same amount of native coins be released to `user` on the
+    /// home chain.
+    pub fn withdraw(&mut self, user: Address, value: U256) -> Result<(), Revert> {
+        self.foreign.transact(
+            user,
+            self.token,
+            U256::zero(),
+            &abi::encode_call(
+                "transferAndCall(address,uint256,bytes)",
+                &[
+                    Token::Address(self.foreign_bridge),
+                    Token::Uint(value),
+                    Token::Bytes(Vec::new()),
+                ],
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// The token balance of `user` on the foreign chain.
+    pub fn token_balance(&mut self, user: Address) -> Result<U256, Revert> {
+        let output = self.foreign.transact(
+            user,
+            self.token,
+            U256::zero(),
+            &abi::encode_call("balanceOf(address)", &[Token::Address(user)]),
+        )?;
+        Ok(abi::decode_uint(&abi::words(&output)[0]))
+    }
+
+    /// Does what the validators do for every request made since the last
+    /// relay, in both directions.  Returns the number of requests relayed.
+    ///
+    /// Stops at the first transaction that fails.  The request it was for is
+    /// not retried by later relays.
+    pub fn relay(&mut self) -> Result<usize, Revert> {
+        Ok(self.relay_to_foreign()? + self.relay_to_home()?)
+    }
+
+    /// Has `required_signatures` validators sign each `UserRequestForSignature`
+    /// made on the home chain since the last relay, and relays the collected
+    /// signatures to the foreign bridge.
+    pub fn relay_to_foreign(&mut self) -> Result<usize, Revert> {
+        let topic = event_topic("UserRequestForSignature(address,uint256)");
+        let requests: Vec<_> = self.home.logs()[self.home_logs_seen..]
+            .iter()
+            .enumerate()
+            .filter(|(_, log)| {
+                log.address == self.home_bridge && log.topics.first() == Some(&topic)
+            })
+            .map(|(index, log)| (self.home_logs_seen + index, abi::words(&log.data)))
+            .collect();
+        self.home_logs_seen = self.home.logs().len();
+        for &(index, ref data) in &requests {
+            let message = self.message(
+                abi::decode_address(&data[0]),
+                abi::decode_uint(&data[1]),
+                transaction_hash(self.home_bridge, index),
+            );
+            for validator in 0..self.required_signatures {
+                self.submit_signature(validator, &message)?;
+            }
+            self.execute_signatures(keccak(&message))?;
+        }
+        Ok(requests.len())
+    }
+
+    /// Has `required_signatures` validators affirm each
+    /// `UserRequestForAffirmation` made on the foreign chain since the last
+    /// relay.
+    pub fn relay_to_home(&mut self) -> Result<usize, Revert> {
+        let topic = event_topic("UserRequestForAffirmation(address,uint256)");
+        let requests: Vec<_> = self.foreign.logs()[self.foreign_logs_seen..]
+            .iter()
+            .enumerate()
+            .filter(|(_, log)| {
+                log.address == self.foreign_bridge && log.topics.first() == Some(&topic)
+            })
+            .map(|(index, log)| (self.foreign_logs_seen + index, abi::words(&log.data)))
+            .collect();
+        self.foreign_logs_seen = self.foreign.logs().len();
+        for &(index, ref data) in &requests {
+            let transaction_hash = transaction_hash(self.foreign_bridge, index);
+            for validator in 0..self.required_signatures {
+                self.execute_affirmation(
+                    validator,
+                    abi::decode_address(&data[0]),
+                    abi::decode_uint(&data[1]),
+                    transaction_hash,
+                )?;
+            }
+        }
+        Ok(requests.len())
+    }
+
+    /// The message that the validators sign to release `amount` to
+    /// `recipient` on the foreign chain, in the format accepted by
+    /// `parse_message::parse_message`.
+    pub fn message(&self, recipient: Address, amount: U256, transaction_hash: H256) -> Vec<u8> {
+        let mut message = Vec::with_capacity(REQUIRED_MESSAGE_LENGTH);
+        message.extend_from_slice(&recipient[..]);
+        let mut amount_bytes = [0; 32];
+        amount.to_little_endian(&mut amount_bytes);
+        message.extend_from_slice(&amount_bytes);
+        message.extend_from_slice(&transaction_hash[..]);
+        message.extend_from_slice(&self.foreign_bridge[..]);
+        message
+    }
+
+    /// Has the `validator`th validator sign `message` and submit the
+    /// signature to the home bridge.
+    pub fn submit_signature(&mut self, validator: usize, message: &[u8]) -> Result<(), Revert> {
+        let validator = &self.validators[validator];
+        let input = abi::encode_call(
+            "submitSignature(bytes,bytes)",
+            &[
+                Token::Bytes(validator.sign(message)),
+                Token::Bytes(message.to_vec()),
+            ],
+        );
+        self.home
+            .transact(validator.address, self.home_bridge, U256::zero(), &input)?;
+        Ok(())
+    }
+
+    /// Reads the message with hash `message_hash` and the signatures
+    /// collected for it from the home bridge, and relays them to the foreign
+    /// bridge from the validator responsible for the relay.
+    pub fn execute_signatures(&mut self, message_hash: H256) -> Result<(), Revert> {
+        let collected = self
+            .home
+            .events(
+                self.home_bridge,
+                "CollectedSignatures(address,bytes32,uint256)",
+            )
+            .into_iter()
+            .map(|log| abi::words(&log.data))
+            .find(|data| H256::from(data[1]) == message_hash)
+            .ok_or_else(|| Revert::new("Signatures not collected"))?;
+        let relayer = abi::decode_address(&collected[0]);
+        let count = abi::decode_uint(&collected[2]);
+
+        let message = self.home_view(
+            "message(bytes32)",
+            &[Token::FixedBytes(message_hash.into())],
+        )?;
+        let (mut vs, mut rs, mut ss) = (Vec::new(), Vec::new(), Vec::new());
+        for index in 0..count.low_u64() {
+            let signature = self.home_view(
+                "signature(bytes32,uint256)",
+                &[
+                    Token::FixedBytes(message_hash.into()),
+                    Token::Uint(index.into()),
+                ],
+            )?;
+            let mut r = [0; 32];
+            let mut s = [0; 32];
+            r.copy_from_slice(&signature[..32]);
+            s.copy_from_slice(&signature[32..64]);
+            rs.push(Token::FixedBytes(r));
+            ss.push(Token::FixedBytes(s));
+            vs.push(signature[64]);
+        }
+        let input = abi::encode_call(
+            "executeSignatures(bytes,bytes32[],bytes32[],bytes)",
+            &[
+                Token::Bytes(vs),
+                Token::Array(rs),
+                Token::Array(ss),
+                Token::Bytes(message),
+            ],
+        );
+        self.foreign
+            .transact(relayer, self.foreign_bridge, U256::zero(), &input)?;
+        Ok(())
+    }
+
+    /// Has the `validator`th validator affirm the transfer of `value` to
+    /// `recipient` made on the foreign chain in `transaction_hash`.
+    pub fn execute_affirmation(
+        &mut self,
+        validator: usize,
+        recipient: Address,
+        value: U256,
+        transaction_hash: H256,
+    ) -> Result<(), Revert> {
+        let input = abi::encode_call(
+            "executeAffirmation(address,uint256,bytes32)",
+            &[
+                Token::Address(recipient),
+                Token::Uint(value),
+                Token::FixedBytes(transaction_hash.into()),
+            ],
+        );
+        self.home.transact(
+            self.validators[validator].address,
+            self.home_bridge,
+            U256::zero(),
+            &input,
+        )?;
+        Ok(())
+    }
+
+    /// Calls a method of the home bridge that returns `bytes`.
+    fn home_view(&mut self, signature: &str, tokens: &[Token]) -> Result<Vec<u8>, Revert> {
+        let output = self.home.transact(
+            self.owner,
+            self.home_bridge,
+            U256::zero(),
+            &abi::encode_call(signature, tokens),
+        )?;
+        Ok(abi::decode_bytes(&output))
+    }
+}
+
+/// Stands in for the hash of the transaction that emitted the `index`th log
+/// of a chain, which the simulator does not have.
+fn transaction_hash(contract: Address, index: usize) -> H256 {
+    let mut data = contract.to_vec();
+    data.extend_from_slice(&(index as u64).to_be_bytes());
+    keccak(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::BridgeError;
+    use crate::simulator::abi::{decode_address, decode_uint, words};
+    use crate::simulator::Contract;
+    use std::vec;
+
+    fn ether(amount: u64) -> U256 {
+        U256::from(10).pow(18.into()) * U256::from(amount)
+    }
+
+    fn user() -> Address {
+        Address::from([0x55; 20])
+    }
+
+    fn bridge() -> Bridge {
+        let mut bridge = Bridge::new(3, 2, Limits::default()).unwrap();
+        bridge.home.set_balance(user(), ether(10));
+        bridge
+    }
+
+    /// Calls `method` of the home bridge proxy as the owner.
+    fn home_proxy(bridge: &mut Bridge, method: &str, args: &[Token]) -> Result<Vec<u8>, Revert> {
+        let (owner, home_bridge) = (bridge.owner, bridge.home_bridge);
+        bridge.home.transact(
+            owner,
+            home_bridge,
+            U256::zero(),
+            &abi::encode_call(method, args),
+        )
+    }
+
+    fn home_implementation(bridge: &mut Bridge) -> Address {
+        decode_address(&words(&home_proxy(bridge, "implementation()", &[]).unwrap())[0])
+    }
+
+    #[test]
+    fn deploys_validators_on_both_chains() {
+        let mut bridge = bridge();
+        let owner = bridge.owner;
+        let input = abi::encode_call("validatorList()", &[]);
+        let expected: Vec<_> = bridge.validators.iter().map(|v| v.address).collect();
+        let (home_validators, foreign_validators) =
+            (bridge.home_validators, bridge.foreign_validators);
+        for (chain, validators) in vec![
+            (&mut bridge.home, home_validators),
+            (&mut bridge.foreign, foreign_validators),
+        ] {
+            let output = words(
+                &chain
+                    .transact(owner, validators, U256::zero(), &input)
+                    .unwrap(),
+            );
+            let listed: Vec<_> = output[2..].iter().map(decode_address).collect();
+            assert_eq!(listed, expected);
+        }
+    }
+
+    #[test]
+    fn relays_deposit() {
+        let mut bridge = bridge();
+        bridge.deposit(user(), ether(2)).unwrap();
+        assert_eq!(bridge.home.balance(user()), ether(8));
+        assert_eq!(bridge.home.balance(bridge.home_bridge), ether(2));
+        assert_eq!(bridge.token_balance(user()).unwrap(), U256::zero());
+        assert_eq!(bridge.relay().unwrap(), 1);
+        assert_eq!(bridge.token_balance(user()).unwrap(), ether(2));
+        // Nothing is relayed twice.
+        assert_eq!(bridge.relay().unwrap(), 0);
+        assert_eq!(bridge.token_balance(user()).unwrap(), ether(2));
+    }
+
+    #[test]
+    fn relays_withdrawal() {
+        let mut bridge = bridge();
+        bridge.deposit(user(), ether(2)).unwrap();
+        bridge.relay().unwrap();
+        bridge.withdraw(user(), ether(1)).unwrap();
+        assert_eq!(bridge.token_balance(user()).unwrap(), ether(1));
+        assert_eq!(bridge.home.balance(user()), ether(8));
+        assert_eq!(bridge.relay().unwrap(), 1);
+        assert_eq!(bridge.home.balance(user()), ether(9));
+        assert_eq!(bridge.home.balance(bridge.home_bridge), ether(1));
+    }
+
+    /// A contract that reverts on every call, including plain transfers.
+    fn reverting_recipient(_input: &[u8]) -> Vec<u8> {
+        crate::ext::panic(&[])
+    }
+
+    #[test]
+    fn keeps_coins_if_recipient_reverts() {
+        let mut bridge = bridge();
+        bridge.deposit(user(), ether(2)).unwrap();
+        let owner = bridge.owner;
+        let recipient = bridge
+            .home
+            .deploy(
+                owner,
+                Contract {
+                    run: reverting_recipient,
+                    construct: None,
+                },
+                &[],
+            )
+            .unwrap();
+        let transaction_hash = H256::from([0x42; 32]);
+        bridge
+            .execute_affirmation(0, recipient, ether(1), transaction_hash)
+            .unwrap();
+        assert_eq!(
+            bridge
+                .execute_affirmation(1, recipient, ether(1), transaction_hash)
+                .unwrap_err()
+                .error,
+            Some(BridgeError::ExecutionFailed)
+        );
+        assert_eq!(bridge.home.balance(recipient), U256::zero());
+        assert_eq!(bridge.home.balance(bridge.home_bridge), ether(2));
+    }
+
+    #[test]
+    fn rejects_deposit_without_funds() {
+        let mut bridge = bridge();
+        let revert = bridge.deposit(user(), ether(11)).unwrap_err();
+        assert_eq!(revert.error, None);
+        assert_eq!(
+            revert.reason.as_ref().map(|s| &s[..]),
+            Some("Insufficient balance")
+        );
+        assert_eq!(bridge.home.balance(user()), ether(10));
+        assert_eq!(
+            bridge.deposit(user(), U256::zero()).unwrap_err().error,
+            Some(BridgeError::ZeroValue)
+        );
+    }
+
+    #[test]
+    fn upgrades_home_bridge_mid_flow() {
+        let mut bridge = bridge();
+        let original = home_implementation(&mut bridge);
+        home_proxy(
+            &mut bridge,
+            "setUpgradeDelay(uint256)",
+            &[Token::Uint(3600.into())],
+        )
+        .unwrap();
+
+        // A deposit is made, and an upgrade scheduled, before the relay.
+        bridge.deposit(user(), ether(1)).unwrap();
+        let owner = bridge.owner;
+        let upgraded = bridge
+            .home
+            .deploy(owner, HOME_BRIDGE_NATIVE_TO_ERC, &[])
+            .unwrap();
+        let eta = U256::from(bridge.home.timestamp() + 3600);
+        home_proxy(
+            &mut bridge,
+            "scheduleUpgrade(uint256,address,uint256)",
+            &[
+                Token::Uint(2.into()),
+                Token::Address(upgraded),
+                Token::Uint(eta),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            home_proxy(
+                &mut bridge,
+                "upgradeTo(uint256,address)",
+                &[Token::Uint(2.into()), Token::Address(upgraded)],
+            )
+            .unwrap_err()
+            .error,
+            Some(BridgeError::UpgradeMustBeScheduled)
+        );
+        assert_eq!(
+            home_proxy(&mut bridge, "executeUpgrade()", &[])
+                .unwrap_err()
+                .error,
+            Some(BridgeError::UpgradeNotDue)
+        );
+        assert_eq!(bridge.relay().unwrap(), 1);
+        assert_eq!(bridge.token_balance(user()).unwrap(), ether(1));
+
+        // The upgrade goes live once due, and the bridge keeps its state.
+        bridge.home.mine(300, 3600);
+        home_proxy(&mut bridge, "executeUpgrade()", &[]).unwrap();
+        assert_eq!(home_implementation(&mut bridge), upgraded);
+        let history = words(
+            &home_proxy(
+                &mut bridge,
+                "upgradeHistory(uint256)",
+                &[Token::Uint(1.into())],
+            )
+            .unwrap(),
+        );
+        assert_eq!(decode_uint(&history[0]), 2.into());
+        assert_eq!(decode_address(&history[1]), upgraded);
+        assert_eq!(decode_uint(&history[2]), bridge.home.block_number().into());
+        bridge.deposit(user(), ether(1)).unwrap();
+        bridge.withdraw(user(), ether(1)).unwrap();
+        assert_eq!(bridge.relay().unwrap(), 2);
+        assert_eq!(bridge.token_balance(user()).unwrap(), ether(1));
+        assert_eq!(bridge.home.balance(user()), ether(9));
+
+        // Rolling back is subject to the same delay, and the bridge still
+        // works afterwards.
+        assert_eq!(
+            home_proxy(&mut bridge, "rollbackTo(uint256)", &[Token::Uint(1.into())])
+                .unwrap_err()
+                .error,
+            Some(BridgeError::UpgradeMustBeScheduled)
+        );
+        let eta = U256::from(bridge.home.timestamp() + 3600);
+        home_proxy(
+            &mut bridge,
+            "scheduleRollback(uint256,uint256)",
+            &[Token::Uint(1.into()), Token::Uint(eta)],
+        )
+        .unwrap();
+        assert_eq!(home_implementation(&mut bridge), upgraded);
+        bridge.home.mine(300, 3600);
+        home_proxy(&mut bridge, "executeUpgrade()", &[]).unwrap();
+        assert_eq!(home_implementation(&mut bridge), original);
+        bridge.deposit(user(), ether(1)).unwrap();
+        assert_eq!(bridge.relay().unwrap(), 1);
+        assert_eq!(bridge.token_balance(user()).unwrap(), ether(2));
+        assert_eq!(bridge.home.balance(bridge.home_bridge), ether(2));
+    }
+}
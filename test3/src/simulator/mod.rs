@@ -0,0 +1,541 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! An in-process simulator for running the contracts on one or more chains.
+//!
+//! A [`Chain`] is an in-memory host: a set of accounts, each with its own
+//! storage and, for contracts, the native entry point of its code, together
+//! with the balances, block, and event log of the chain.  Contracts run
+//! natively against [`ext::native`](crate::ext::native), and calls between
+//! them, including delegate calls from the proxy, are routed to the callee
+//! on the same chain.  A call that panics is reverted, along with everything
+//! it did.
+//!
+//! [`bridge::Bridge`] builds a home and a foreign chain on top of this and
+//! relays transfers between them.
+//!
+//! The simulator is only built for tests.  It catches panics, so it cannot
+//! be used with `panic = "abort"`, which the release profile sets.
+pub mod abi;
+pub mod bridge;
+
+use self::abi::Token;
+use crate::error::BridgeError;
+use crate::ext::native::{self, CallKind, CallRecord, Externalities, LogEntry, Return};
+use pwasm_std::keccak;
+use pwasm_std::types::{Address, H256, U256};
+use std::{
+    any::Any,
+    boxed::Box,
+    cell::RefCell,
+    collections::BTreeMap,
+    mem, panic,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// The native entry points of a contract.
+#[derive(Clone, Copy)]
+pub struct Contract {
+    /// Runs the contract on its call data, returning its output.
+    pub run: fn(&[u8]) -> Vec<u8>,
+    /// Runs the constructor on its arguments, if the contract has one.
+    pub construct: Option<fn(&[u8])>,
+}
+
+/// `BridgeValidators`.
+pub const BRIDGE_VALIDATORS: Contract = Contract {
+    run: crate::upgradeable_contracts::run_bridge_validators,
+    construct: Some(crate::upgradeable_contracts::construct_bridge_validators),
+};
+
+/// `OwnedUpgradeabilityProxy`.
+pub const PROXY: Contract = Contract {
+    run: crate::upgradeability::run,
+    construct: Some(crate::upgradeability::construct),
+};
+
+/// `ERC677BridgeToken`.
+pub const ERC677_TOKEN: Contract = Contract {
+    run: crate::erc677_bridge_token::run,
+    construct: Some(crate::erc677_bridge_token::construct),
+};
+
+/// The native-to-ERC20 home bridge.
+pub const HOME_BRIDGE_NATIVE_TO_ERC: Contract = Contract {
+    run: crate::upgradeable_contracts::native_to_erc20::home_bridge::run,
+    construct: None,
+};
+
+/// The native-to-ERC20 foreign bridge.
+pub const FOREIGN_BRIDGE_NATIVE_TO_ERC: Contract = Contract {
+    run: crate::upgradeable_contracts::native_to_erc20::foreign_bridge::run,
+    construct: None,
+};
+
+/// The ERC20-to-ERC20 home bridge.
+pub const HOME_BRIDGE_ERC_TO_ERC: Contract = Contract {
+    run: crate::upgradeable_contracts::erc20_to_erc20::home_bridge::run,
+    construct: None,
+};
+
+/// The ERC20-to-ERC20 foreign bridge.
+pub const FOREIGN_BRIDGE_ERC_TO_ERC: Contract = Contract {
+    run: crate::upgradeable_contracts::erc20_to_erc20::foreign_bridge::run,
+    construct: None,
+};
+
+/// The ERC20-to-native home bridge.
+pub const HOME_BRIDGE_ERC_TO_NATIVE: Contract = Contract {
+    run: crate::upgradeable_contracts::erc20_to_native::home_bridge::run,
+    construct: None,
+};
+
+/// `MultiSigWallet`.
+pub const MULTISIG_WALLET: Contract = Contract {
+    run: crate::multisig_wallet::run,
+    construct: Some(crate::multisig_wallet::construct),
+};
+
+/// Why a transaction failed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Revert {
+    /// The error the contract reverted with, if it was a [`BridgeError`].
+    pub error: Option<BridgeError>,
+    /// The reason the contract reverted or panicked with, if it gave one.
+    pub reason: Option<String>,
+    /// The data the contract reverted with.  Empty if it panicked.
+    pub data: Vec<u8>,
+}
+
+impl Revert {
+    fn new(reason: &str) -> Self {
+        Revert {
+            error: None,
+            reason: Some(reason.to_string()),
+            data: Vec::new(),
+        }
+    }
+
+    fn from_payload(payload: Box<dyn Any + Send>) -> Self {
+        let payload = match payload.downcast::<native::Panic>() {
+            Ok(data) => {
+                let error = BridgeError::decode(&data.0);
+                return Revert {
+                    error,
+                    reason: error
+                        .map(|error| String::from_utf8_lossy(&error.reason()).into_owned()),
+                    data: data.0,
+                };
+            }
+            Err(payload) => payload,
+        };
+        let reason = match payload.downcast::<String>() {
+            Ok(reason) => Some(*reason),
+            Err(payload) => payload.downcast_ref::<&str>().map(|s| s.to_string()),
+        };
+        Revert {
+            error: None,
+            reason,
+            data: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Account {
+    code: Option<fn(&[u8]) -> Vec<u8>>,
+    storage: BTreeMap<H256, [u8; 32]>,
+}
+
+#[derive(Default)]
+struct State {
+    accounts: BTreeMap<Address, Account>,
+    /// The number of contracts deployed, used to pick their addresses.
+    deployed: u64,
+}
+
+impl State {
+    /// Moves the storage of the running contract back into its account.
+    fn store(&mut self, ext: &mut Externalities) {
+        let storage = mem::replace(&mut ext.storage, BTreeMap::new());
+        self.accounts.entry(ext.address).or_default().storage = storage;
+    }
+
+    /// Moves the storage of the account of the running contract out to the
+    /// externalities.
+    fn load(&mut self, ext: &mut Externalities) {
+        let account = self.accounts.entry(ext.address).or_default();
+        ext.storage = mem::replace(&mut account.storage, BTreeMap::new());
+    }
+}
+
+/// An in-memory chain.
+pub struct Chain {
+    state: Rc<RefCell<State>>,
+    /// The externalities of this chain, which are moved into the thread's
+    /// externalities while a transaction runs.
+    ext: Externalities,
+    /// Distinguishes the addresses of contracts deployed on this chain from
+    /// those on other chains.
+    id: u8,
+}
+
+impl Chain {
+    /// Creates an empty chain.  Chains with different `id`s deploy contracts
+    /// to different addresses.
+    pub fn new(id: u8) -> Self {
+        let state = Rc::new(RefCell::new(State::default()));
+        let mut ext = Externalities::default();
+        let handler_state = state.clone();
+        ext.handle_calls(move |record: &CallRecord| {
+            let body = handler_state
+                .borrow()
+                .accounts
+                .get(&record.address)
+                .and_then(|account| account.code);
+            message_call(&handler_state, record, body.map(Body::Run)).map_err(|revert| revert.data)
+        });
+        Chain { state, ext, id }
+    }
+
+    /// Runs `f` with the externalities of this chain installed as those of
+    /// the current thread.
+    fn enter<R, F: FnOnce(&Rc<RefCell<State>>) -> R>(&mut self, f: F) -> R {
+        let ext = &mut self.ext;
+        native::with(|current| mem::swap(current, ext));
+        let result = f(&self.state);
+        let ext = &mut self.ext;
+        native::with(|current| mem::swap(current, ext));
+        result
+    }
+
+    /// Deploys `contract` from `from`, running its constructor on
+    /// `arguments`.  Returns the address of the new contract.  As on a real
+    /// chain, the new contract has no code size until its constructor
+    /// returns.
+    pub fn deploy(
+        &mut self,
+        from: Address,
+        contract: Contract,
+        arguments: &[u8],
+    ) -> Result<Address, Revert> {
+        let address = {
+            let mut state = self.state.borrow_mut();
+            state.deployed += 1;
+            let mut address = [0; 20];
+            address[0] = self.id;
+            address[12..].copy_from_slice(&state.deployed.to_be_bytes());
+            let address = Address::from(address);
+            state.accounts.entry(address).or_default().code = Some(contract.run);
+            address
+        };
+        let result = match contract.construct {
+            Some(construct) => self.message_call(
+                from,
+                address,
+                U256::zero(),
+                arguments,
+                Some(Body::Construct(construct)),
+            ),
+            None => Ok(Vec::new()),
+        };
+        match result {
+            Ok(_) => {
+                // Contracts run natively and have no bytecode, so each one
+                // is taken to be a single byte long.
+                self.ext.code_sizes.insert(address, 1);
+                Ok(address)
+            }
+            Err(revert) => {
+                self.state.borrow_mut().accounts.remove(&address);
+                Err(revert)
+            }
+        }
+    }
+
+    /// Deploys `contract` and an `OwnedUpgradeabilityProxy` in front of it,
+    /// both from `from`, and upgrades the proxy to version 1 of `contract`.
+    /// Returns the address of the proxy.
+    pub fn deploy_behind_proxy(
+        &mut self,
+        from: Address,
+        contract: Contract,
+    ) -> Result<Address, Revert> {
+        let implementation = self.deploy(from, contract, &[])?;
+        let proxy = self.deploy(from, PROXY, &[])?;
+        self.transact(
+            from,
+            proxy,
+            U256::zero(),
+            &abi::encode_call(
+                "upgradeTo(uint256,address)",
+                &[Token::Uint(1.into()), Token::Address(implementation)],
+            ),
+        )?;
+        Ok(proxy)
+    }
+
+    /// Sends a transaction from `from` to `to`, transferring `value`, with
+    /// call data `input`.  Returns the output of the call.
+    pub fn transact(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+        input: &[u8],
+    ) -> Result<Vec<u8>, Revert> {
+        let body = self.code(to).map(Body::Run);
+        self.message_call(from, to, value, input, body)
+    }
+
+    fn message_call(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+        input: &[u8],
+        body: Option<Body>,
+    ) -> Result<Vec<u8>, Revert> {
+        self.enter(|state| {
+            native::with(|ext| {
+                ext.sender = Address::zero();
+                ext.value = U256::zero();
+                ext.address = from;
+                ext.input = Vec::new();
+                state.borrow_mut().load(ext);
+            });
+            let record = CallRecord {
+                kind: CallKind::Call,
+                gas: native::gas_left(),
+                address: to,
+                value,
+                input: input.to_vec(),
+            };
+            let result = message_call(state, &record, body);
+            native::with(|ext| state.borrow_mut().store(ext));
+            result
+        })
+    }
+
+    /// Returns the code of the contract at `address`, if there is one.
+    pub fn code(&self, address: Address) -> Option<fn(&[u8]) -> Vec<u8>> {
+        self.state
+            .borrow()
+            .accounts
+            .get(&address)
+            .and_then(|account| account.code)
+    }
+
+    pub fn balance(&self, address: Address) -> U256 {
+        self.ext.balances.get(&address).cloned().unwrap_or_default()
+    }
+
+    pub fn set_balance(&mut self, address: Address, balance: U256) {
+        self.ext.balances.insert(address, balance);
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.ext.block_number
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.ext.timestamp
+    }
+
+    /// Mines `blocks` empty blocks, over `seconds` seconds.
+    pub fn mine(&mut self, blocks: u64, seconds: u64) {
+        self.ext.block_number += blocks;
+        self.ext.timestamp += seconds;
+    }
+
+    /// Every log emitted on this chain, in order.
+    pub fn logs(&self) -> &[LogEntry] {
+        &self.ext.logs
+    }
+
+    /// The logs emitted by `address` for the event with the canonical
+    /// signature `event`, e.g. `"Transfer(address,address,uint256)"`.
+    pub fn events(&self, address: Address, event: &str) -> Vec<LogEntry> {
+        let topic = event_topic(event);
+        self.ext
+            .logs
+            .iter()
+            .filter(|log| log.address == address && log.topics.first() == Some(&topic))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The first topic of the logs for the event with the canonical signature
+/// `event`.
+pub fn event_topic(event: &str) -> H256 {
+    keccak(event.as_bytes())
+}
+
+/// The code run by a message call.
+#[derive(Clone, Copy)]
+enum Body {
+    Run(fn(&[u8]) -> Vec<u8>),
+    Construct(fn(&[u8])),
+}
+
+/// The parts of the externalities that a message call replaces, and
+/// restores when it returns.
+struct Frame {
+    sender: Address,
+    value: U256,
+    address: Address,
+    input: Vec<u8>,
+}
+
+/// What a message call must undo if it fails.
+struct Snapshot {
+    accounts: BTreeMap<Address, Account>,
+    balances: BTreeMap<Address, U256>,
+    logs: usize,
+}
+
+/// Performs the call described by `record`, made by the running contract,
+/// by running `body` in a new frame.  An account with no code accepts any
+/// call and returns nothing.
+///
+/// If `body` panics, everything the call did is undone.  A panic with a
+/// [`Return`] payload is how `ret` stops a contract, and is not a failure.
+fn message_call(
+    state: &Rc<RefCell<State>>,
+    record: &CallRecord,
+    body: Option<Body>,
+) -> Result<Vec<u8>, Revert> {
+    let (frame, snapshot) = native::with(|ext| {
+        let mut state = state.borrow_mut();
+        state.store(ext);
+        let snapshot = Snapshot {
+            accounts: state.accounts.clone(),
+            balances: ext.balances.clone(),
+            logs: ext.logs.len(),
+        };
+        let frame = Frame {
+            sender: ext.sender,
+            value: ext.value,
+            address: ext.address,
+            input: mem::replace(&mut ext.input, record.input.clone()),
+        };
+        if record.kind == CallKind::Call {
+            ext.sender = ext.address;
+            ext.value = record.value;
+            ext.address = record.address;
+        }
+        state.load(ext);
+        (frame, snapshot)
+    });
+
+    let result = transfer(&frame.address, record).and_then(|()| match body {
+        None => Ok(Vec::new()),
+        Some(body) => {
+            let input = &record.input;
+            panic::catch_unwind(panic::AssertUnwindSafe(|| match body {
+                Body::Run(run) => run(input),
+                Body::Construct(construct) => {
+                    construct(input);
+                    Vec::new()
+                }
+            }))
+            .or_else(|payload| match payload.downcast::<Return>() {
+                Ok(output) => Ok(output.0),
+                Err(payload) => Err(Revert::from_payload(payload)),
+            })
+        }
+    });
+
+    native::with(|ext| {
+        let mut state = state.borrow_mut();
+        state.store(ext);
+        if result.is_err() {
+            state.accounts = snapshot.accounts;
+            ext.balances = snapshot.balances;
+            ext.logs.truncate(snapshot.logs);
+        }
+        ext.sender = frame.sender;
+        ext.value = frame.value;
+        ext.address = frame.address;
+        ext.input = frame.input;
+        state.load(ext);
+    });
+    result
+}
+
+/// Moves the value sent with a call from the caller to the callee.
+fn transfer(from: &Address, record: &CallRecord) -> Result<(), Revert> {
+    if record.kind != CallKind::Call || record.value.is_zero() {
+        return Ok(());
+    }
+    native::with(|ext| {
+        let balance = ext.balances.get(from).cloned().unwrap_or_default();
+        if balance < record.value {
+            return Err(Revert::new("Insufficient balance"));
+        }
+        ext.balances.insert(*from, balance - record.value);
+        let to = ext.balances.entry(record.address).or_default();
+        *to = *to + record.value;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec;
+
+    #[test]
+    fn runs_every_bridge_mode_behind_proxy() {
+        let mut chain = Chain::new(1);
+        let owner = Address::from([0xee; 20]);
+        for &contract in &[
+            HOME_BRIDGE_NATIVE_TO_ERC,
+            FOREIGN_BRIDGE_NATIVE_TO_ERC,
+            HOME_BRIDGE_ERC_TO_ERC,
+            FOREIGN_BRIDGE_ERC_TO_ERC,
+            HOME_BRIDGE_ERC_TO_NATIVE,
+        ] {
+            let bridge = chain.deploy_behind_proxy(owner, contract).unwrap();
+            let output = chain
+                .transact(
+                    owner,
+                    bridge,
+                    U256::zero(),
+                    &abi::encode_call("isInitialized()", &[]),
+                )
+                .unwrap();
+            assert_eq!(output, vec![0; 32]);
+        }
+    }
+
+    #[test]
+    fn reverts_failed_deploy() {
+        let mut chain = Chain::new(1);
+        let revert = chain
+            .deploy(
+                Address::from([0xee; 20]),
+                MULTISIG_WALLET,
+                &abi::encode(&[Token::Array(vec![]), Token::Uint(0.into())]),
+            )
+            .unwrap_err();
+        assert_eq!(revert.error, Some(BridgeError::InvalidRequirement));
+        assert!(chain.logs().is_empty());
+    }
+}
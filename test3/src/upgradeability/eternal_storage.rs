@@ -14,6 +14,223 @@
 //
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+//! Storage shared by all upgradeable contracts.
+//!
+//! This mirrors the Solidity `EternalStorage` contract: values live in six
+//! typed maps, each keyed by `keccak256(name ++ key)`.  Because every
+//! upgradeable contract reads and writes its state through these maps, the
+//! storage layout is the same no matter which implementation the proxy points
+//! to, and it survives upgrades.
+use crate::error::{require, revert, BridgeError};
+use crate::ext::{read, write};
+use pwasm_std::{
+    keccak,
+    types::{Address, H256, U256},
+    String, Vec,
+};
+
+/// The position of each map in the Solidity `EternalStorage` contract.  The
+/// slot of an entry is `keccak256(key ++ position)`, exactly as Solidity lays
+/// out a `mapping(bytes32 => T)`.
+const UINT_STORAGE: u8 = 0;
+const STRING_STORAGE: u8 = 1;
+const ADDRESS_STORAGE: u8 = 2;
+const BYTES_STORAGE: u8 = 3;
+const BOOL_STORAGE: u8 = 4;
+const INT_STORAGE: u8 = 5;
+
+/// Computes the key of the entry `key` under `name`, i.e.
+/// `keccak256(abi.encodePacked(name, key))`.
+pub fn key(name: &[u8], key: &[u8]) -> H256 {
+    let mut buf = Vec::with_capacity(name.len() + key.len());
+    buf.extend_from_slice(name);
+    buf.extend_from_slice(key);
+    keccak(&buf)
+}
+
+/// Returns the storage slot of `key` in the map at `position`.
+fn slot(position: u8, key: &H256) -> H256 {
+    let mut buf = [0; 64];
+    buf[..32].copy_from_slice(&key[..]);
+    buf[63] = position;
+    keccak(&buf[..])
+}
+
+/// Returns the slot `index` slots after `base`.
+fn offset(base: &H256, index: usize) -> H256 {
+    let base = U256::from(<[u8; 32]>::from(*base));
+    H256::from(<[u8; 32]>::from(base + U256::from(index)))
+}
+
+fn read_u256(slot: &H256) -> U256 {
+    U256::from(read(slot))
+}
+
+fn write_u256(slot: &H256, value: U256) {
+    write(slot, &value.into())
+}
+
+fn read_bool(slot: &H256) -> bool {
+    !read_u256(slot).is_zero()
+}
+
+fn write_bool(slot: &H256, value: bool) {
+    write_u256(slot, U256::from(value as u8))
+}
+
+/// Reads a byte string stored by `write_bytes`.
+pub(crate) fn read_bytes(slot: &H256) -> Vec<u8> {
+    let len = read_u256(slot);
+    require(len <= core::usize::MAX.into(), BridgeError::CorruptStorage);
+    let len = len.low_u64() as usize;
+    let base = keccak(slot);
+    let mut data = Vec::with_capacity(len);
+    let mut index = 0;
+    while data.len() < len {
+        let chunk = read(&offset(&base, index));
+        let remaining = core::cmp::min(len - data.len(), 32);
+        data.extend_from_slice(&chunk[..remaining]);
+        index += 1;
+    }
+    data
+}
+
+/// Stores a byte string across multiple slots.  The length is stored at
+/// `slot`, and the data itself is stored in 32-byte chunks starting at
+/// `keccak(slot)`, the same layout Solidity uses for `bytes`.
+pub(crate) fn write_bytes(slot: &H256, data: &[u8]) {
+    write_u256(slot, data.len().into());
+    let base = keccak(slot);
+    for (index, chunk) in data.chunks(32).enumerate() {
+        let mut buf = [0; 32];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        write(&offset(&base, index), &buf);
+    }
+}
 
 /// This contract holds all the necessary state variables to carry out the storage of any contract.
-struct EternalStorage {}
+///
+/// All methods have default implementations, so a contract only needs to
+/// `impl EternalStorage for MyContract {}`.  Keys should be computed with
+/// [`key`].
+pub trait EternalStorage {
+    fn uintStorage(&self, key: H256) -> U256 {
+        read_u256(&slot(UINT_STORAGE, &key))
+    }
+
+    fn setUintStorage(&mut self, key: H256, value: U256) {
+        write_u256(&slot(UINT_STORAGE, &key), value)
+    }
+
+    fn stringStorage(&self, key: H256) -> String {
+        String::from_utf8(read_bytes(&slot(STRING_STORAGE, &key)))
+            .unwrap_or_else(|_| revert(BridgeError::CorruptStorage))
+    }
+
+    fn setStringStorage(&mut self, key: H256, value: &str) {
+        write_bytes(&slot(STRING_STORAGE, &key), value.as_bytes())
+    }
+
+    fn addressStorage(&self, key: H256) -> Address {
+        H256::from(read(&slot(ADDRESS_STORAGE, &key))).into()
+    }
+
+    fn setAddressStorage(&mut self, key: H256, value: Address) {
+        write(&slot(ADDRESS_STORAGE, &key), &H256::from(value).into())
+    }
+
+    fn bytesStorage(&self, key: H256) -> Vec<u8> {
+        read_bytes(&slot(BYTES_STORAGE, &key))
+    }
+
+    fn setBytesStorage(&mut self, key: H256, value: &[u8]) {
+        write_bytes(&slot(BYTES_STORAGE, &key), value)
+    }
+
+    fn boolStorage(&self, key: H256) -> bool {
+        read_bool(&slot(BOOL_STORAGE, &key))
+    }
+
+    fn setBoolStorage(&mut self, key: H256, value: bool) {
+        write_bool(&slot(BOOL_STORAGE, &key), value)
+    }
+
+    /// Signed integers are stored as a 256-bit word in two's complement,
+    /// big-endian, as in Solidity.
+    fn intStorage(&self, key: H256) -> [u8; 32] {
+        read(&slot(INT_STORAGE, &key))
+    }
+
+    fn setIntStorage(&mut self, key: H256, value: [u8; 32]) {
+        write(&slot(INT_STORAGE, &key), &value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::native;
+
+    struct Storage;
+
+    impl EternalStorage for Storage {}
+
+    #[test]
+    fn uint_entry_uses_solidity_mapping_slot() {
+        native::reset();
+        let entry = key(b"dailyLimit", &[]);
+        Storage.setUintStorage(entry, 42.into());
+        let mut preimage = [0; 64];
+        preimage[..32].copy_from_slice(&entry[..]);
+        preimage[63] = UINT_STORAGE;
+        assert_eq!(U256::from(read(&keccak(&preimage[..]))), 42.into());
+        assert_eq!(Storage.uintStorage(entry), 42.into());
+    }
+
+    #[test]
+    fn maps_do_not_overlap() {
+        native::reset();
+        let entry = key(b"entry", &[]);
+        Storage.setUintStorage(entry, 1.into());
+        assert!(!Storage.boolStorage(entry));
+        assert_eq!(Storage.addressStorage(entry), Address::zero());
+        assert_eq!(Storage.intStorage(entry), [0; 32]);
+    }
+
+    #[test]
+    fn int_keeps_all_256_bits() {
+        native::reset();
+        let entry = key(b"entry", &[]);
+        let minus_one = [0xff; 32];
+        Storage.setIntStorage(entry, minus_one);
+        assert_eq!(Storage.intStorage(entry), minus_one);
+        let mut min = [0; 32];
+        min[0] = 0x80;
+        Storage.setIntStorage(entry, min);
+        assert_eq!(Storage.intStorage(entry), min);
+    }
+
+    #[test]
+    fn bytes_span_several_slots() {
+        native::reset();
+        let entry = key(b"entry", &[]);
+        let data: Vec<u8> = (0..70).collect();
+        Storage.setBytesStorage(entry, &data);
+        assert_eq!(Storage.bytesStorage(entry), data);
+        let slot = slot(BYTES_STORAGE, &entry);
+        assert_eq!(read_u256(&slot), 70.into());
+        assert_eq!(&read(&keccak(&slot))[..], &data[..32]);
+        Storage.setBytesStorage(entry, &data[..3]);
+        assert_eq!(Storage.bytesStorage(entry), &data[..3]);
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        native::reset();
+        let entry = key(b"entry", &[]);
+        assert_eq!(Storage.stringStorage(entry), "");
+        Storage.setStringStorage(entry, "Bridge Token");
+        assert_eq!(Storage.stringStorage(entry), "Bridge Token");
+    }
+}
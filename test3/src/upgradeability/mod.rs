@@ -16,13 +16,88 @@
 // this program.  If not, see <https://www.gnu.org/licenses/>.
 #![allow(non_snake_case, dead_code)]
 pub mod eternal_storage;
+pub mod proxy;
 pub mod upgradeability_storage;
 
+use self::eternal_storage::EternalStorage;
+use self::upgradeability_storage::UpgradeabilityStorageTrait;
+use crate::error::{require, BridgeError};
+use crate::ext::{block_number, call_code, gas_left, panic, return_data, sender, timestamp};
+use crate::upgradeable_contracts::access_control::{
+    AccessControlInternal, AccessControlTrait, DEFAULT_ADMIN_ROLE, UPGRADER_ROLE,
+};
 use pwasm_abi_derive::eth_abi;
-use pwasm_ethereum::{address, call, call_code, gas_left, sender, value};
 use pwasm_std::types::{Address, U256};
 use pwasm_std::Vec;
 
+include!(concat!(env!("OUT_DIR"), "/proxy_storage.rs"));
+
+/// Methods implemented by the proxy itself.  Calls to any other method are
+/// delegated to the implementation.
+pub const PROXY_METHODS: &[&str] = &[
+    "proxyOwner()",
+    "pendingProxyOwner()",
+    "transferProxyOwnership(address)",
+    "claimProxyOwnership()",
+    "cancelProxyOwnershipTransfer()",
+    "upgradeTo(uint256,address)",
+    "upgradeToAndCall(uint256,address,bytes)",
+    "upgradeDelay()",
+    "setUpgradeDelay(uint256)",
+    "scheduledUpgradeDelay()",
+    "executeUpgradeDelay()",
+    "scheduledUpgrade()",
+    "scheduleUpgrade(uint256,address,uint256)",
+    "scheduleUpgradeAndCall(uint256,address,bytes,uint256)",
+    "scheduledUpgradeData()",
+    "executeUpgrade()",
+    "cancelUpgrade()",
+    "latestVersion()",
+    "implementationAt(uint256)",
+    "upgradeHistoryLength()",
+    "upgradeHistory(uint256)",
+    "rollbackTo(uint256)",
+    "scheduleRollback(uint256,uint256)",
+    "scheduledRollback()",
+];
+
+/// The longest delay that can be required between scheduling and executing
+/// an upgrade, so that a mistake cannot lock the proxy forever: 30 days.
+const MAX_UPGRADE_DELAY: u64 = 30 * 24 * 60 * 60;
+
+/// Runs the proxy on the call data `input`, returning its output.  Calls that
+/// are not to the proxy itself are delegated to the implementation, and do
+/// not return.
+#[cfg(any(feature = "proxy", not(target_arch = "wasm32")))]
+pub fn run(input: &[u8]) -> Vec<u8> {
+    use self::proxy::Proxy;
+    use self::upgradeability_storage::UpgradeabilityStorage;
+    use crate::dispatch::calls_any;
+    use crate::upgradeable_contracts::access_control::{AccessControl, ACCESS_CONTROL_METHODS};
+    // Declares the dispatch and dispatch_ctor methods
+    use pwasm_abi::eth::EndpointInterface;
+    // Read http://solidity.readthedocs.io/en/develop/abi-spec.html#formal-specification-of-the-encoding for details
+    if calls_any(input, PROXY_METHODS) {
+        OwnedUpgradeabilityProxy::new(OwnedUpgradeabilityProxyImpl).dispatch(input)
+    } else if calls_any(input, STORAGE_METHODS) {
+        UpgradeabilityStorage::new(OwnedUpgradeabilityProxyImpl).dispatch(input)
+    } else if calls_any(input, ACCESS_CONTROL_METHODS) {
+        AccessControl::new(OwnedUpgradeabilityProxyImpl).dispatch(input)
+    } else {
+        OwnedUpgradeabilityProxyImpl.delegate()
+    }
+}
+
+/// Runs the constructor of the proxy on `input`.
+#[cfg(any(feature = "proxy", not(target_arch = "wasm32")))]
+pub fn construct(input: &[u8]) {
+    use pwasm_abi::eth::EndpointInterface;
+    OwnedUpgradeabilityProxy::new(OwnedUpgradeabilityProxyImpl).dispatch_ctor(input)
+}
+
+/// Methods of `UpgradeabilityStorage`, which the proxy also implements.
+pub const STORAGE_METHODS: &[&str] = &["version()", "implementation()"];
+
 mod internal {
     pub struct Q;
     pub trait UpgradeabilityProxy {
@@ -38,7 +113,7 @@ mod internal {
  * @dev This contract combines an upgradeability proxy with basic authorization control functionalities
  */
 #[eth_abi(OwnedUpgradeabilityProxy)]
-trait OwnedUpgradeabilityProxyTrait {
+pub trait OwnedUpgradeabilityProxyTrait {
     /**
      * @dev Event to show ownership has been transferred
      * @param previousOwner representing the address of the previous owner
@@ -47,43 +122,70 @@ trait OwnedUpgradeabilityProxyTrait {
     #[event]
     fn ProxyOwnershipTransferred(previousOwner: Address, newOwner: Address);
 
-    /// The constructor sets the original owner of the contract to the sender account.
+    /// Event to show that a transfer of proxy ownership has been started,
+    /// and that `newOwner` may now claim it.
+    #[event]
+    fn ProxyOwnershipTransferStarted(previousOwner: Address, newOwner: Address);
+
+    /// Event to show that a pending transfer of proxy ownership was cancelled.
+    #[event]
+    fn ProxyOwnershipTransferCancelled(pendingOwner: Address);
+
+    /// The constructor sets the original owner of the contract to the sender
+    /// account, and grants it the admin and upgrader roles.
     fn constructor(&mut self);
 
     /// Returns the address of the proxy owner.
     #[constant]
     fn proxyOwner(&self) -> Address;
 
-    /// Asserts that the current owner is the sender of the message.
+    /// Returns the address that may claim ownership of the proxy, or the zero
+    /// address if no transfer is pending.
+    #[constant]
+    fn pendingProxyOwner(&self) -> Address;
+
+    /// Allows the current owner to start transferring control of the contract
+    /// to address `newOwner`.  Ownership only changes once `newOwner` calls
+    /// `claimProxyOwnership`.
     ///
     /// # Panics
     ///
-    /// Panics if the current execution was not triggered by the owner of this contract.
-    fn onlyProxyOwner(&self) {
-        assert_eq!(sender(), self.proxyOwner())
-    }
+    /// Panics if `msg.sender` is not the current owner, or if `newOwner` is
+    /// the zero address.
+    fn transferProxyOwnership(&mut self, newOwner: Address);
 
-    /// Allows the current owner to transfer control of the contract to address `newOwner`.
+    /// Completes a transfer of proxy ownership.  The admin and upgrader roles
+    /// move from the previous owner to the new one.
     ///
     /// # Panics
     ///
-    /// Panics if `msg.sender` is not the current owner.
-    fn transferProxyOwnership(&mut self, newOwner: Address);
+    /// Panics if `msg.sender` is not the pending owner.
+    fn claimProxyOwnership(&mut self);
 
-    /// Allows the upgradeability owner to upgrade the current version of the proxy.
+    /// Cancels a pending transfer of proxy ownership.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not the current owner, or if no transfer is
+    /// pending.
+    fn cancelProxyOwnershipTransfer(&mut self);
+
+    /// Allows an upgrader to upgrade the current version of the proxy.
     /// The new implementation address is set to `implementation`,
     /// and the new version is set to `version`.
     ///
     /// `implementation` must not be the zero address.  `version` must be strictly
-    /// greater than the current version.
+    /// greater than every version the proxy has pointed to.
     ///
     /// # Panics
     ///
     /// Panics if any of the following are true:
     ///
     /// * `implementation` is the zero address.
-    /// * `version` is &le; the old version.
-    /// * `msg.sender` is not the current owner of the contract.
+    /// * `version` is &le; `latestVersion`.
+    /// * `msg.sender` does not have `UPGRADER_ROLE`.
+    /// * `upgradeDelay` is not zero, in which case upgrades must be
+    ///   scheduled with `scheduleUpgrade`.
     fn upgradeTo(&mut self, version: U256, implementation: Address);
 
     /**
@@ -93,11 +195,18 @@ trait OwnedUpgradeabilityProxyTrait {
      * @param implementation representing the address of the new implementation to be set.
      * @param data represents the msg.data to bet sent in the low level call. This parameter may include the function
      * signature of the implementation to be called with the needed payload
+     *
+     * The call is delegated, as any other call to the proxy would be, so the
+     * implementation sees the upgrader as `msg.sender` and may let it
+     * initialize the contract.  If the call reverts, so does the upgrade,
+     * with the same revert data.
      */
     //#[payable]
     fn upgradeToAndCall(&mut self, version: U256, implementation: Address, data: Vec<u8>) {
         self.upgradeTo(version, implementation);
-        call(gas_left(), &address(), value(), &data, &mut []).expect("Upgrade failed")
+        if call_code(gas_left(), &implementation, &data, &mut []).is_err() {
+            panic(&return_data())
+        }
     }
 
     /**
@@ -107,42 +216,880 @@ trait OwnedUpgradeabilityProxyTrait {
      */
     #[event]
     fn Upgraded(version: U256, indexed_implementation: Address);
+
+    /// Fired when an upgrade is scheduled to be executed at time `eta` or
+    /// later.
+    #[event]
+    fn UpgradeScheduled(version: U256, indexed_implementation: Address, eta: U256);
+
+    /// Fired when a scheduled upgrade is cancelled.
+    #[event]
+    fn UpgradeCancelled(version: U256, indexed_implementation: Address);
+
+    /// Fired when the minimum delay of scheduled upgrades changes.
+    #[event]
+    fn UpgradeDelayChanged(delay: U256);
+
+    /// Fired when a decrease of the minimum delay of scheduled upgrades is
+    /// scheduled to take effect at time `eta` or later.
+    #[event]
+    fn UpgradeDelayChangeScheduled(delay: U256, eta: U256);
+
+    /// Returns the minimum time, in seconds, between scheduling an upgrade
+    /// and executing it.  While it is zero, `upgradeTo` and
+    /// `upgradeToAndCall` take effect immediately, and so does `rollbackTo`.
+    #[constant]
+    fn upgradeDelay(&self) -> U256;
+
+    /// Changes the minimum delay of scheduled upgrades.  Upgrades that are
+    /// already scheduled keep their `eta`.
+    ///
+    /// An increase, or the current delay, takes effect immediately and
+    /// cancels any scheduled decrease.  A decrease would let an upgrade go live sooner than users
+    /// were promised, so it is itself scheduled `upgradeDelay` seconds
+    /// ahead, and takes effect with `executeUpgradeDelay`.  It replaces any
+    /// decrease that is already scheduled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an admin, or if `delay` is more than 30
+    /// days.
+    fn setUpgradeDelay(&mut self, delay: U256);
+
+    /// Returns the scheduled decrease of `upgradeDelay` and its `eta`, or
+    /// zeros if there is none.
+    #[constant]
+    fn scheduledUpgradeDelay(&self) -> (U256, U256);
+
+    /// Makes the scheduled decrease of `upgradeDelay` take effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an admin, if no decrease is scheduled,
+    /// or if its `eta` has not been reached.
+    fn executeUpgradeDelay(&mut self);
+
+    /// Returns the version, implementation, and `eta` of the scheduled
+    /// upgrade, or zeros if there is none.
+    #[constant]
+    fn scheduledUpgrade(&self) -> (U256, Address, U256);
+
+    /// Schedules an upgrade to `implementation` and `version`, to be executed
+    /// with `executeUpgrade` at time `eta` or later.  Replaces any upgrade
+    /// that is already scheduled, firing `UpgradeCancelled` for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` does not have `UPGRADER_ROLE`, if `eta` is
+    /// less than `upgradeDelay` seconds from now, or if the upgrade would be
+    /// rejected by `upgradeTo`.
+    fn scheduleUpgrade(&mut self, version: U256, implementation: Address, eta: U256);
+
+    /// Schedules an upgrade as `scheduleUpgrade` does.  Once it is executed,
+    /// `data` is delegated to the new implementation as `upgradeToAndCall`
+    /// does, so that an upgrade behind a delay can still initialize the
+    /// contract in the same transaction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scheduleUpgrade` would.
+    fn scheduleUpgradeAndCall(
+        &mut self,
+        version: U256,
+        implementation: Address,
+        data: Vec<u8>,
+        eta: U256,
+    );
+
+    /// Returns the data that the scheduled upgrade will delegate to the new
+    /// implementation, or an empty byte string if there is none.
+    #[constant]
+    fn scheduledUpgradeData(&self) -> Vec<u8>;
+
+    /// Executes the scheduled upgrade, or the scheduled rollback if
+    /// `scheduledRollback` is true.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` does not have `UPGRADER_ROLE`, if no upgrade
+    /// is scheduled, if its `eta` has not been reached, or if the call to
+    /// the new implementation reverts, with the same revert data.
+    fn executeUpgrade(&mut self);
+
+    /// Cancels the scheduled upgrade.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` does not have `UPGRADER_ROLE`, or if no
+    /// upgrade is scheduled.
+    fn cancelUpgrade(&mut self);
+
+    /// Fired when the proxy is rolled back to an earlier version.
+    #[event]
+    fn RolledBack(version: U256, indexed_implementation: Address);
+
+    /// Returns the highest version the proxy has pointed to.  New
+    /// implementations must have a higher version than this, even after a
+    /// rollback.
+    #[constant]
+    fn latestVersion(&self) -> U256;
+
+    /// Returns the implementation of `version`, or the zero address if the
+    /// proxy has never been upgraded to `version`.
+    #[constant]
+    fn implementationAt(&self, version: U256) -> Address;
+
+    /// Returns the number of entries in the upgrade history.
+    #[constant]
+    fn upgradeHistoryLength(&self) -> U256;
+
+    /// Returns the version, the implementation, and the block number of entry
+    /// `index` of the upgrade history.  An entry is recorded every time the
+    /// proxy is upgraded or rolled back.
+    #[constant]
+    fn upgradeHistory(&self, index: U256) -> (U256, Address, U256);
+
+    /// Points the proxy back at the implementation of `version`, which it
+    /// must have been upgraded to before.
+    ///
+    /// An old version may have been retired for a bug, so a rollback is
+    /// subject to `upgradeDelay` just like an upgrade: while the delay is not
+    /// zero, rollbacks must be scheduled with `scheduleRollback`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` does not have `UPGRADER_ROLE`, if
+    /// `upgradeDelay` is not zero, if the proxy has never been upgraded to
+    /// `version`, or if `version` is the current version.
+    fn rollbackTo(&mut self, version: U256);
+
+    /// Fired when a rollback is scheduled to be executed at time `eta` or
+    /// later.
+    #[event]
+    fn RollbackScheduled(version: U256, indexed_implementation: Address, eta: U256);
+
+    /// Schedules a rollback to `version`, to be executed with
+    /// `executeUpgrade` at time `eta` or later.  It takes the place of the
+    /// scheduled upgrade, and can be cancelled with `cancelUpgrade`.
+    /// Replaces any upgrade that is already scheduled, firing
+    /// `UpgradeCancelled` for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` does not have `UPGRADER_ROLE`, if `eta` is
+    /// less than `upgradeDelay` seconds from now, if the proxy has never been
+    /// upgraded to `version`, or if `version` is the current version.
+    fn scheduleRollback(&mut self, version: U256, eta: U256);
+
+    /// Returns whether the scheduled upgrade is a rollback.
+    #[constant]
+    fn scheduledRollback(&self) -> bool;
 }
 
+/// Internal helpers for [`OwnedUpgradeabilityProxyTrait`] that must not be
+/// part of the ABI.  The proxy delegates every method it does not implement,
+/// so a method here would be unreachable anyway.
+pub trait OwnedUpgradeabilityProxyInternal: OwnedUpgradeabilityProxyTrait {
+    /// Asserts that the current owner is the sender of the message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current execution was not triggered by the owner of this contract.
+    fn onlyProxyOwner(&self) {
+        require(sender() == self.proxyOwner(), BridgeError::NotOwner)
+    }
+}
+
+impl<T: OwnedUpgradeabilityProxyTrait + ?Sized> OwnedUpgradeabilityProxyInternal for T {}
+
 /// # UpgradeabilityOwnerStorage
 ///
-/// This contract keeps track of the upgradeability owner
-struct OwnedUpgradeabilityProxyImpl {
-    /// Contract version
-    version: U256,
-    /// Implementation
-    implementation: Address,
-    /// Owner of the contract
-    upgradeability_owner: Address,
+/// This contract keeps track of the upgradeability owner.  All of its state
+/// lives in the slots declared in `proxy.storage`, so that it persists between
+/// calls and does not collide with the storage of the implementation.
+pub struct OwnedUpgradeabilityProxyImpl;
+
+impl EternalStorage for OwnedUpgradeabilityProxyImpl {}
+
+/// The proxy reads its roles from eternal storage, so they are shared with
+/// the implementation.
+impl AccessControlTrait for OwnedUpgradeabilityProxyImpl {}
+
+impl UpgradeabilityStorageTrait for OwnedUpgradeabilityProxyImpl {
+    fn version(&self) -> U256 {
+        get_version()
+    }
+
+    fn implementation(&self) -> Address {
+        get_implementation()
+    }
 }
 
 impl OwnedUpgradeabilityProxyTrait for OwnedUpgradeabilityProxyImpl {
     fn transferProxyOwnership(&mut self, newOwner: Address) {
         self.onlyProxyOwner();
-        assert_ne!(newOwner, Address::zero());
-        self.ProxyOwnershipTransferred(sender(), newOwner);
-        self.upgradeability_owner = newOwner;
+        require(newOwner != Address::zero(), BridgeError::ZeroAddress);
+        self.ProxyOwnershipTransferStarted(sender(), newOwner);
+        set_pendingUpgradeabilityOwner(newOwner);
+    }
+
+    fn claimProxyOwnership(&mut self) {
+        let newOwner = self.pendingProxyOwner();
+        require(
+            newOwner != Address::zero(),
+            BridgeError::NoOwnershipTransferPending,
+        );
+        require(sender() == newOwner, BridgeError::NotPendingOwner);
+        self.transfer_roles(self.proxyOwner(), newOwner, &[UPGRADER_ROLE]);
+        self.ProxyOwnershipTransferred(self.proxyOwner(), newOwner);
+        set_upgradeabilityOwner(newOwner);
+        set_pendingUpgradeabilityOwner(Address::zero());
+    }
+
+    fn cancelProxyOwnershipTransfer(&mut self) {
+        self.onlyProxyOwner();
+        let pendingOwner = self.pendingProxyOwner();
+        require(
+            pendingOwner != Address::zero(),
+            BridgeError::NoOwnershipTransferPending,
+        );
+        self.ProxyOwnershipTransferCancelled(pendingOwner);
+        set_pendingUpgradeabilityOwner(Address::zero());
     }
 
     fn proxyOwner(&self) -> Address {
-        self.upgradeability_owner
+        get_upgradeabilityOwner()
+    }
+
+    fn pendingProxyOwner(&self) -> Address {
+        get_pendingUpgradeabilityOwner()
     }
 
     fn constructor(&mut self) {
-        self.upgradeability_owner = sender()
+        set_upgradeabilityOwner(sender());
+        self.setup_roles(sender(), &[UPGRADER_ROLE]);
     }
 
     fn upgradeTo(&mut self, version: U256, implementation: Address) {
-        self.onlyProxyOwner();
-        assert_ne!(sender(), implementation);
-        assert!(version > self.version);
-        self.version = version;
-        self.implementation = implementation;
+        self.onlyRole(UPGRADER_ROLE);
+        require(
+            self.upgradeDelay().is_zero(),
+            BridgeError::UpgradeMustBeScheduled,
+        );
+        self.upgrade(version, implementation);
+    }
+
+    fn upgradeDelay(&self) -> U256 {
+        get_upgradeDelay()
+    }
+
+    fn setUpgradeDelay(&mut self, delay: U256) {
+        self.onlyRole(DEFAULT_ADMIN_ROLE);
+        require(delay <= MAX_UPGRADE_DELAY.into(), BridgeError::DelayTooLong);
+        let current = self.upgradeDelay();
+        if delay >= current {
+            self.clear_scheduled_upgrade_delay();
+            set_upgradeDelay(delay);
+            self.UpgradeDelayChanged(delay);
+        } else {
+            let eta = U256::from(timestamp()) + current;
+            set_scheduledUpgradeDelay(delay);
+            set_scheduledUpgradeDelayEta(eta);
+            self.UpgradeDelayChangeScheduled(delay, eta);
+        }
+    }
+
+    fn scheduledUpgradeDelay(&self) -> (U256, U256) {
+        (get_scheduledUpgradeDelay(), get_scheduledUpgradeDelayEta())
+    }
+
+    fn executeUpgradeDelay(&mut self) {
+        self.onlyRole(DEFAULT_ADMIN_ROLE);
+        let (delay, eta) = self.scheduledUpgradeDelay();
+        require(!eta.is_zero(), BridgeError::NoDelayChangeScheduled);
+        require(
+            U256::from(timestamp()) >= eta,
+            BridgeError::DelayChangeNotDue,
+        );
+        self.clear_scheduled_upgrade_delay();
+        set_upgradeDelay(delay);
+        self.UpgradeDelayChanged(delay);
+    }
+
+    fn scheduledUpgrade(&self) -> (U256, Address, U256) {
+        (
+            get_scheduledVersion(),
+            get_scheduledImplementation(),
+            get_scheduledEta(),
+        )
+    }
+
+    fn scheduleUpgrade(&mut self, version: U256, implementation: Address, eta: U256) {
+        self.scheduleUpgradeAndCall(version, implementation, Vec::new(), eta)
+    }
+
+    fn scheduleUpgradeAndCall(
+        &mut self,
+        version: U256,
+        implementation: Address,
+        data: Vec<u8>,
+        eta: U256,
+    ) {
+        self.onlyRole(UPGRADER_ROLE);
+        self.check_upgrade(version, implementation);
+        self.schedule(version, implementation, eta, false);
+        set_scheduledData(&data);
+        self.UpgradeScheduled(version, implementation, eta);
+    }
+
+    fn scheduledUpgradeData(&self) -> Vec<u8> {
+        get_scheduledData()
+    }
+
+    fn executeUpgrade(&mut self) {
+        self.onlyRole(UPGRADER_ROLE);
+        let (version, implementation, eta) = self.scheduledUpgrade();
+        require(
+            implementation != Address::zero(),
+            BridgeError::NoUpgradeScheduled,
+        );
+        require(U256::from(timestamp()) >= eta, BridgeError::UpgradeNotDue);
+        let rollback = self.scheduledRollback();
+        let data = self.scheduledUpgradeData();
+        self.clear_scheduled_upgrade();
+        if rollback {
+            self.roll_back(version);
+        } else {
+            self.upgrade(version, implementation);
+            if !data.is_empty() && call_code(gas_left(), &implementation, &data, &mut []).is_err() {
+                panic(&return_data())
+            }
+        }
+    }
+
+    fn cancelUpgrade(&mut self) {
+        self.onlyRole(UPGRADER_ROLE);
+        let (version, implementation, _) = self.scheduledUpgrade();
+        require(
+            implementation != Address::zero(),
+            BridgeError::NoUpgradeScheduled,
+        );
+        self.clear_scheduled_upgrade();
+        self.UpgradeCancelled(version, implementation);
+    }
+
+    fn latestVersion(&self) -> U256 {
+        // Proxies upgraded before the history was kept have no latest
+        // version recorded.
+        get_latestVersion().max(self.version())
+    }
+
+    fn implementationAt(&self, version: U256) -> Address {
+        get_implementationAt(version)
+    }
+
+    fn upgradeHistoryLength(&self) -> U256 {
+        get_upgradeHistoryLength()
+    }
+
+    fn upgradeHistory(&self, index: U256) -> (U256, Address, U256) {
+        (
+            get_upgradeHistoryVersion(index),
+            get_upgradeHistoryImplementation(index),
+            get_upgradeHistoryBlock(index),
+        )
+    }
+
+    fn rollbackTo(&mut self, version: U256) {
+        self.onlyRole(UPGRADER_ROLE);
+        require(
+            self.upgradeDelay().is_zero(),
+            BridgeError::UpgradeMustBeScheduled,
+        );
+        self.roll_back(version);
+    }
+
+    fn scheduleRollback(&mut self, version: U256, eta: U256) {
+        self.onlyRole(UPGRADER_ROLE);
+        let implementation = self.check_rollback(version);
+        self.schedule(version, implementation, eta, true);
+        self.RollbackScheduled(version, implementation, eta);
+    }
+
+    fn scheduledRollback(&self) -> bool {
+        get_scheduledRollback()
+    }
+}
+
+impl OwnedUpgradeabilityProxyImpl {
+    /// Asserts that the proxy may be upgraded to `implementation` and
+    /// `version`.
+    fn check_upgrade(&self, version: U256, implementation: Address) {
+        require(implementation != Address::zero(), BridgeError::ZeroAddress);
+        require(
+            implementation != self.implementation(),
+            BridgeError::SameImplementation,
+        );
+        require(version > self.latestVersion(), BridgeError::InvalidVersion);
+    }
+
+    /// Points the proxy at a new `implementation` and `version`.
+    fn upgrade(&mut self, version: U256, implementation: Address) {
+        self.check_upgrade(version, implementation);
+        set_latestVersion(version);
+        set_implementationAt(version, implementation);
+        self.point_to(version, implementation);
         self.Upgraded(version, implementation);
     }
+
+    /// Asserts that the proxy may be rolled back to `version`, and returns
+    /// the implementation of `version`.
+    fn check_rollback(&self, version: U256) -> Address {
+        let implementation = self.implementationAt(version);
+        require(
+            implementation != Address::zero(),
+            BridgeError::UnknownVersion,
+        );
+        require(version != self.version(), BridgeError::AlreadyAtVersion);
+        implementation
+    }
+
+    /// Points the proxy back at the implementation of `version`.
+    fn roll_back(&mut self, version: U256) {
+        let implementation = self.check_rollback(version);
+        self.point_to(version, implementation);
+        self.RolledBack(version, implementation);
+    }
+
+    /// Schedules an upgrade, or a rollback if `rollback` is true, replacing
+    /// any that is already scheduled.
+    fn schedule(&mut self, version: U256, implementation: Address, eta: U256, rollback: bool) {
+        require(
+            eta >= U256::from(timestamp()) + self.upgradeDelay(),
+            BridgeError::UpgradeTooSoon,
+        );
+        let (replaced_version, replaced_implementation, _) = self.scheduledUpgrade();
+        if replaced_implementation != Address::zero() {
+            self.UpgradeCancelled(replaced_version, replaced_implementation);
+        }
+        set_scheduledVersion(version);
+        set_scheduledImplementation(implementation);
+        set_scheduledEta(eta);
+        set_scheduledRollback(rollback);
+        set_scheduledData(&[]);
+    }
+
+    /// Points the proxy at `implementation` and `version`, and records it in
+    /// the upgrade history.
+    fn point_to(&mut self, version: U256, implementation: Address) {
+        set_version(version);
+        set_implementation(implementation);
+        let index = self.upgradeHistoryLength();
+        set_upgradeHistoryVersion(index, version);
+        set_upgradeHistoryImplementation(index, implementation);
+        set_upgradeHistoryBlock(index, block_number().into());
+        set_upgradeHistoryLength(index + U256::from(1));
+    }
+
+    fn clear_scheduled_upgrade(&mut self) {
+        set_scheduledVersion(U256::zero());
+        set_scheduledImplementation(Address::zero());
+        set_scheduledEta(U256::zero());
+        set_scheduledRollback(false);
+        set_scheduledData(&[]);
+    }
+
+    fn clear_scheduled_upgrade_delay(&mut self) {
+        set_scheduledUpgradeDelay(U256::zero());
+        set_scheduledUpgradeDelayEta(U256::zero());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatch::method_id;
+    use crate::ext::native::{self, CallKind, Return};
+    use crate::simulator::event_topic;
+    use pwasm_std::types::H256;
+    use std::{panic::catch_unwind, vec};
+
+    fn admin() -> Address {
+        Address::from([0xaa; 20])
+    }
+
+    fn implementation(i: u8) -> Address {
+        Address::from([i; 20])
+    }
+
+    /// Deploys the proxy from `admin`.
+    fn deploy() {
+        native::reset();
+        native::with(|ext| ext.sender = admin());
+        OwnedUpgradeabilityProxyImpl.constructor();
+    }
+
+    fn reverts_with<R>(f: impl FnOnce() -> R) -> Option<BridgeError> {
+        BridgeError::decode(&native::catch_revert(f).err()?)
+    }
+
+    fn wait(seconds: u64) {
+        native::with(|ext| ext.timestamp += seconds);
+    }
+
+    fn topics() -> Vec<H256> {
+        native::with(|ext| ext.logs.iter().map(|log| log.topics[0]).collect())
+    }
+
+    #[test]
+    fn raises_delay_immediately() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(100.into());
+        assert_eq!(OwnedUpgradeabilityProxyImpl.upgradeDelay(), 100.into());
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.upgradeTo(1.into(), implementation(1))),
+            Some(BridgeError::UpgradeMustBeScheduled)
+        );
+    }
+
+    #[test]
+    fn schedules_lowering_of_delay() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(100.into());
+        wait(5);
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(0.into());
+        assert_eq!(OwnedUpgradeabilityProxyImpl.upgradeDelay(), 100.into());
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.scheduledUpgradeDelay(),
+            (0.into(), 105.into())
+        );
+        assert_eq!(
+            topics().last(),
+            Some(&event_topic("UpgradeDelayChangeScheduled(uint256,uint256)"))
+        );
+        wait(99);
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.executeUpgradeDelay()),
+            Some(BridgeError::DelayChangeNotDue)
+        );
+        native::with(|ext| ext.sender = implementation(9));
+        wait(1);
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.executeUpgradeDelay()),
+            Some(BridgeError::MissingRole)
+        );
+        native::with(|ext| ext.sender = admin());
+        OwnedUpgradeabilityProxyImpl.executeUpgradeDelay();
+        assert_eq!(OwnedUpgradeabilityProxyImpl.upgradeDelay(), 0.into());
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.scheduledUpgradeDelay(),
+            (0.into(), 0.into())
+        );
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.executeUpgradeDelay()),
+            Some(BridgeError::NoDelayChangeScheduled)
+        );
+        OwnedUpgradeabilityProxyImpl.upgradeTo(1.into(), implementation(1));
+    }
+
+    #[test]
+    fn raising_delay_cancels_lowering() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(100.into());
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(10.into());
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(100.into());
+        wait(100);
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.executeUpgradeDelay()),
+            Some(BridgeError::NoDelayChangeScheduled)
+        );
+        assert_eq!(OwnedUpgradeabilityProxyImpl.upgradeDelay(), 100.into());
+    }
+
+    #[test]
+    fn rescheduling_cancels_scheduled_upgrade() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(10.into());
+        OwnedUpgradeabilityProxyImpl.scheduleUpgrade(1.into(), implementation(1), 10.into());
+        let logs = topics().len();
+        OwnedUpgradeabilityProxyImpl.scheduleUpgrade(2.into(), implementation(2), 20.into());
+        assert_eq!(
+            topics()[logs..].to_vec(),
+            vec![
+                event_topic("UpgradeCancelled(uint256,address)"),
+                event_topic("UpgradeScheduled(uint256,address,uint256)"),
+            ]
+        );
+        wait(20);
+        OwnedUpgradeabilityProxyImpl.executeUpgrade();
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.implementation(),
+            implementation(2)
+        );
+    }
+
+    #[test]
+    fn upgrades_and_records_history() {
+        deploy();
+        native::with(|ext| ext.block_number = 7);
+        OwnedUpgradeabilityProxyImpl.upgradeTo(1.into(), implementation(1));
+        assert_eq!(OwnedUpgradeabilityProxyImpl.version(), 1.into());
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.implementation(),
+            implementation(1)
+        );
+        assert_eq!(OwnedUpgradeabilityProxyImpl.latestVersion(), 1.into());
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.implementationAt(1.into()),
+            implementation(1)
+        );
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.upgradeHistory(0.into()),
+            (1.into(), implementation(1), 7.into())
+        );
+        assert_eq!(
+            topics().last(),
+            Some(&event_topic("Upgraded(uint256,address)"))
+        );
+
+        for &(version, new_implementation, error) in &[
+            (1, implementation(2), BridgeError::InvalidVersion),
+            (2, implementation(1), BridgeError::SameImplementation),
+            (2, Address::zero(), BridgeError::ZeroAddress),
+        ] {
+            assert_eq!(
+                reverts_with(
+                    || OwnedUpgradeabilityProxyImpl.upgradeTo(version.into(), new_implementation)
+                ),
+                Some(error)
+            );
+        }
+        native::with(|ext| ext.sender = implementation(9));
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.upgradeTo(2.into(), implementation(2))),
+            Some(BridgeError::MissingRole)
+        );
+    }
+
+    #[test]
+    fn rolls_back_immediately_without_delay() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.upgradeTo(1.into(), implementation(1));
+        OwnedUpgradeabilityProxyImpl.upgradeTo(2.into(), implementation(2));
+        OwnedUpgradeabilityProxyImpl.rollbackTo(1.into());
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.implementation(),
+            implementation(1)
+        );
+        assert_eq!(OwnedUpgradeabilityProxyImpl.latestVersion(), 2.into());
+        for &(version, error) in &[
+            (1, BridgeError::AlreadyAtVersion),
+            (3, BridgeError::UnknownVersion),
+        ] {
+            assert_eq!(
+                reverts_with(|| OwnedUpgradeabilityProxyImpl.rollbackTo(version.into())),
+                Some(error)
+            );
+        }
+    }
+
+    #[test]
+    fn rollbacks_must_be_scheduled() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.upgradeTo(1.into(), implementation(1));
+        OwnedUpgradeabilityProxyImpl.upgradeTo(2.into(), implementation(2));
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(10.into());
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.rollbackTo(1.into())),
+            Some(BridgeError::UpgradeMustBeScheduled)
+        );
+        for &(version, eta, error) in &[
+            (1, 9, BridgeError::UpgradeTooSoon),
+            (2, 10, BridgeError::AlreadyAtVersion),
+            (3, 10, BridgeError::UnknownVersion),
+        ] {
+            assert_eq!(
+                reverts_with(
+                    || OwnedUpgradeabilityProxyImpl.scheduleRollback(version.into(), eta.into())
+                ),
+                Some(error)
+            );
+        }
+
+        OwnedUpgradeabilityProxyImpl.scheduleRollback(1.into(), 10.into());
+        assert!(OwnedUpgradeabilityProxyImpl.scheduledRollback());
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.scheduledUpgrade(),
+            (1.into(), implementation(1), 10.into())
+        );
+        assert_eq!(
+            topics().last(),
+            Some(&event_topic("RollbackScheduled(uint256,address,uint256)"))
+        );
+        wait(9);
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.executeUpgrade()),
+            Some(BridgeError::UpgradeNotDue)
+        );
+        wait(1);
+        OwnedUpgradeabilityProxyImpl.executeUpgrade();
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.implementation(),
+            implementation(1)
+        );
+        assert_eq!(OwnedUpgradeabilityProxyImpl.version(), 1.into());
+        assert_eq!(OwnedUpgradeabilityProxyImpl.latestVersion(), 2.into());
+        assert!(!OwnedUpgradeabilityProxyImpl.scheduledRollback());
+        assert_eq!(
+            topics().last(),
+            Some(&event_topic("RolledBack(uint256,address)"))
+        );
+    }
+
+    #[test]
+    fn scheduling_upgrade_replaces_rollback() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.upgradeTo(1.into(), implementation(1));
+        OwnedUpgradeabilityProxyImpl.upgradeTo(2.into(), implementation(2));
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(10.into());
+        OwnedUpgradeabilityProxyImpl.scheduleRollback(1.into(), 10.into());
+        OwnedUpgradeabilityProxyImpl.scheduleUpgrade(3.into(), implementation(3), 10.into());
+        assert!(!OwnedUpgradeabilityProxyImpl.scheduledRollback());
+        wait(10);
+        OwnedUpgradeabilityProxyImpl.executeUpgrade();
+        assert_eq!(OwnedUpgradeabilityProxyImpl.version(), 3.into());
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.implementation(),
+            implementation(3)
+        );
+    }
+
+    #[test]
+    fn schedules_upgrade_and_call() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(10.into());
+        // Longer than a slot, so that it is kept across several.
+        let mut data = method_id("initialize(uint256)").to_vec();
+        data.extend_from_slice(&[7; 64]);
+        data.push(1);
+        OwnedUpgradeabilityProxyImpl.scheduleUpgradeAndCall(
+            1.into(),
+            implementation(1),
+            data.clone(),
+            10.into(),
+        );
+        assert_eq!(OwnedUpgradeabilityProxyImpl.scheduledUpgradeData(), data);
+        native::with(|ext| ext.stub_call(implementation(1), |_| Ok(Vec::new())));
+        wait(10);
+        OwnedUpgradeabilityProxyImpl.executeUpgrade();
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.implementation(),
+            implementation(1)
+        );
+        assert!(OwnedUpgradeabilityProxyImpl
+            .scheduledUpgradeData()
+            .is_empty());
+        let calls = native::with(|ext| ext.calls.clone());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].kind, CallKind::CallCode);
+        assert_eq!(calls[0].input, data);
+
+        // A plain upgrade replaces the data, and makes no call.
+        OwnedUpgradeabilityProxyImpl.scheduleUpgradeAndCall(
+            2.into(),
+            implementation(2),
+            data.clone(),
+            20.into(),
+        );
+        OwnedUpgradeabilityProxyImpl.scheduleUpgrade(2.into(), implementation(2), 20.into());
+        assert!(OwnedUpgradeabilityProxyImpl
+            .scheduledUpgradeData()
+            .is_empty());
+        wait(10);
+        OwnedUpgradeabilityProxyImpl.executeUpgrade();
+        assert_eq!(native::with(|ext| ext.calls.len()), 1);
+    }
+
+    #[test]
+    fn scheduled_call_that_reverts_reverts_upgrade() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.setUpgradeDelay(10.into());
+        native::with(|ext| {
+            ext.stub_call(implementation(1), |_| {
+                Err(BridgeError::AlreadyInitialized.encode())
+            })
+        });
+        OwnedUpgradeabilityProxyImpl.scheduleUpgradeAndCall(
+            1.into(),
+            implementation(1),
+            method_id("initialize()").to_vec(),
+            10.into(),
+        );
+        wait(10);
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.executeUpgrade()),
+            Some(BridgeError::AlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn delegates_calls_not_to_proxy() {
+        deploy();
+        OwnedUpgradeabilityProxyImpl.upgradeTo(1.into(), implementation(1));
+        native::with(|ext| ext.stub_call(implementation(1), |_| Ok(vec![5; 32])));
+
+        let output = run(&method_id("proxyOwner()"));
+        assert_eq!(&output[12..], &admin()[..]);
+        assert!(native::with(|ext| ext.calls.is_empty()));
+
+        let input = method_id("validatorCount()").to_vec();
+        let output = catch_unwind(|| run(&input))
+            .unwrap_err()
+            .downcast::<Return>()
+            .unwrap();
+        assert_eq!(output.0, vec![5; 32]);
+        let calls = native::with(|ext| ext.calls.clone());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].kind, CallKind::CallCode);
+        assert_eq!(calls[0].address, implementation(1));
+        assert_eq!(calls[0].input, input);
+    }
+
+    #[test]
+    fn upgrades_and_calls_as_sender() {
+        deploy();
+        let data = method_id("initialize()").to_vec();
+        native::with(|ext| {
+            ext.stub_call(implementation(1), |_| {
+                if native::sender() == admin() {
+                    Ok(Vec::new())
+                } else {
+                    Err(BridgeError::MissingRole.encode())
+                }
+            })
+        });
+        OwnedUpgradeabilityProxyImpl.upgradeToAndCall(1.into(), implementation(1), data.clone());
+        assert_eq!(
+            OwnedUpgradeabilityProxyImpl.implementation(),
+            implementation(1)
+        );
+        let calls = native::with(|ext| ext.calls.clone());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].kind, CallKind::CallCode);
+        assert_eq!(calls[0].input, data);
+
+        native::with(|ext| {
+            ext.stub_call(implementation(2), |_| {
+                Err(BridgeError::AlreadyInitialized.encode())
+            })
+        });
+        assert_eq!(
+            reverts_with(|| OwnedUpgradeabilityProxyImpl.upgradeToAndCall(
+                2.into(),
+                implementation(2),
+                data.clone()
+            )),
+            Some(BridgeError::AlreadyInitialized)
+        );
+    }
 }
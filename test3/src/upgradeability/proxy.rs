@@ -14,94 +14,102 @@
 //
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <https://www.gnu.org/licenses/>.
-/**
- * # Proxy
- *
- * Gives the possibility to delegate any call to a foreign implementation.
- */
-trait Proxy {
+use super::upgradeability_storage::UpgradeabilityStorageTrait;
+use crate::error::{require, BridgeError};
 
-    /**
-    * @dev Tells the address of the implementation where every call will be delegated.
-    * @return address of the implementation to which it will be delegated
-    */
-    #[constant]
-    fn implementation()
+use crate::ext::{call_code, gas_left, input, panic, ret, return_data};
+use pwasm_std::types::Address;
 
-    /**
-    * @dev Fallback function allowing to perform a delegatecall to the given implementation.
-    * This function will return whatever the implementation call returns
-    */
-    function () external payable {
-        address _impl = implementation();
-        require(_impl != address(0));
-        assembly {
-            /*
-                0x40 is the "free memory slot", meaning a pointer to next slot of empty memory. mload(0x40)
-                loads the data in the free memory slot, so `ptr` is a pointer to the next slot of empty
-                memory. It's needed because we're going to write the return data of delegatecall to the
-                free memory slot.
-            */
-            let ptr := mload(0x40)
-            /*
-                `calldatacopy` is copy calldatasize bytes from calldata
-                First argument is the destination to which data is copied(ptr)
-                Second argument specifies the start position of the copied data.
-                    Since calldata is sort of its own unique location in memory,
-                    0 doesn't refer to 0 in memory or 0 in storage - it just refers to the zeroth byte of calldata.
-                    That's always going to be the zeroth byte of the function selector.
-                Third argument, calldatasize, specifies how much data will be copied.
-                    calldata is naturally calldatasize bytes long (same thing as msg.data.length)
-            */
-            calldatacopy(ptr, 0, calldatasize)
-            /*
-                delegatecall params explained:
-                gas: the amount of gas to provide for the call. `gas` is an Opcode that gives
-                    us the amount of gas still available to execution
+/// # Proxy
+///
+/// Gives the possibility to delegate any call to a foreign implementation.
+pub trait Proxy: UpgradeabilityStorageTrait {
+    /// Fallback function allowing to perform a delegatecall to the given implementation.
+    /// This function will return whatever the implementation call returns.
+    ///
+    /// The call is made with `call_code`, so the implementation runs against
+    /// the proxy's storage and sees the original sender and value.  All
+    /// remaining gas and the complete call input are forwarded, and the
+    /// output of the implementation is returned exactly as it was produced.
+    ///
+    /// # Panics
+    ///
+    /// Panics (and thus reverts) if no implementation has been set.  If the
+    /// implementation reverts, the proxy reverts with the same data.
+    fn delegate(&self) -> ! {
+        let implementation = self.implementation();
+        require(
+            implementation != Address::zero(),
+            BridgeError::NoImplementation,
+        );
+        let result = call_code(gas_left(), &implementation, &input(), &mut []);
+        let data = return_data();
+        match result {
+            Ok(()) => ret(&data),
+            Err(_) => panic(&data),
+        }
+    }
+}
 
-                _impl: address of the contract to delegate to
+impl<T: UpgradeabilityStorageTrait + ?Sized> Proxy for T {}
 
-                ptr: to pass copied data
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::native::{self, CallKind, Panic, Return};
+    use pwasm_std::types::U256;
+    use std::{any::Any, boxed::Box, panic::catch_unwind, vec, vec::Vec};
+
+    struct Delegating(Address);
+
+    impl UpgradeabilityStorageTrait for Delegating {
+        fn version(&self) -> U256 {
+            1.into()
+        }
 
-                calldatasize: loads the size of `bytes memory data`, same as msg.data.length
+        fn implementation(&self) -> Address {
+            self.0
+        }
+    }
 
-                0, 0: These are for the `out` and `outsize` params. Because the output could be dynamic,
-                        these are set to 0, 0 so the output data will not be written to memory. The output
-                        data will be read using `returndatasize` and `returdatacopy` instead.
+    fn delegate(implementation: Address) -> Box<dyn Any + Send> {
+        catch_unwind(|| Delegating(implementation).delegate()).unwrap_err()
+    }
 
-                result: This will be 0 if the call fails and 1 if it succeeds
-            */
-            let result := delegatecall(gas, _impl, ptr, calldatasize, 0, 0)
-            /*
+    #[test]
+    fn forwards_exact_output() {
+        native::reset();
+        let implementation = Address::from([1; 20]);
+        native::with(|ext| {
+            ext.input = vec![0xde, 0xad, 0xbe, 0xef];
+            ext.stub_call(implementation, |_| Ok(vec![7; 33]))
+        });
+        let output = delegate(implementation).downcast::<Return>().unwrap();
+        assert_eq!(output.0, vec![7; 33]);
+        let calls = native::with(|ext| ext.calls.clone());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].kind, CallKind::CallCode);
+        assert_eq!(calls[0].input, vec![0xde, 0xad, 0xbe, 0xef]);
+    }
 
-            */
-            /*
-                ptr current points to the value stored at 0x40,
-                because we assigned it like ptr := mload(0x40).
-                Because we use 0x40 as a free memory pointer,
-                we want to make sure that the next time we want to allocate memory,
-                we aren't overwriting anything important.
-                So, by adding ptr and returndatasize,
-                we get a memory location beyond the end of the data we will be copying to ptr.
-                We place this in at 0x40, and any reads from 0x40 will now read from free memory
-            */
-            mstore(0x40, add(ptr, returndatasize))
-            /*
-                `returndatacopy` is an Opcode that copies the last return data to a slot. `ptr` is the
-                    slot it will copy to, 0 means copy from the beginning of the return data, and size is
-                    the amount of data to copy.
-                `returndatasize` is an Opcode that gives us the size of the last return data. In this case, that is the size of the data returned from delegatecall
-            */
-            returndatacopy(ptr, 0, returndatasize)
+    #[test]
+    fn forwards_revert_data() {
+        native::reset();
+        let implementation = Address::from([1; 20]);
+        let reason: Vec<u8> = BridgeError::MissingRole.encode();
+        let expected = reason.clone();
+        native::with(|ext| ext.stub_call(implementation, move |_| Err(reason.clone())));
+        let payload = delegate(implementation).downcast::<Panic>().unwrap();
+        assert_eq!(payload.0, expected);
+    }
 
-            /*
-                if `result` is 0, revert.
-                if `result` is 1, return `size` amount of data from `ptr`. This is the data that was
-                copied to `ptr` from the delegatecall return data
-            */
-            switch result
-            case 0 { revert(ptr, returndatasize) }
-            default { return(ptr, returndatasize) }
-        }
+    #[test]
+    fn reverts_without_implementation() {
+        native::reset();
+        let payload = delegate(Address::zero()).downcast::<Panic>().unwrap();
+        assert_eq!(
+            BridgeError::decode(&payload.0),
+            Some(BridgeError::NoImplementation)
+        );
     }
 }
@@ -23,7 +23,7 @@ use pwasm_std::types::{Address, U256};
  * This contract holds all the necessary state variables to support the upgrade functionality
  */
 #[eth_abi(UpgradeabilityStorage)]
-trait UpgradeabilityStorageTrait {
+pub trait UpgradeabilityStorageTrait {
     /**
      * Tells the version name of the current implementation.
      *
@@ -39,40 +39,3 @@ trait UpgradeabilityStorageTrait {
     #[constant]
     fn implementation(&self) -> Address;
 }
-
-pub fn create_storage_input(
-    version: U256,
-    implementation: Address,
-) -> impl UpgradeabilityStorageTrait {
-    UpgradeabilityStorageImpl {
-        version,
-        implementation,
-    }
-}
-
-struct UpgradeabilityStorageImpl {
-    // Version name of the current implementation
-    version: U256,
-
-    // Address of the current implementation
-    implementation: Address,
-}
-
-impl UpgradeabilityStorageTrait for UpgradeabilityStorageImpl {
-    /**
-     * Tells the version name of the current implementation.
-     *
-     * Returns a string representing the name of the current version.
-     */
-    fn version(&self) -> U256 {
-        self.version
-    }
-
-    /**
-     * @dev Tells the address of the current implementation
-     * @return address of the current implementation
-     */
-    fn implementation(&self) -> Address {
-        self.implementation
-    }
-}
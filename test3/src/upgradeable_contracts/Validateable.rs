@@ -17,29 +17,23 @@
 #![allow(non_snake_case)]
 
 use super::super::bridge_validators::IBridgeValidators;
-use pwasm_ethereum::sender;
+use crate::error::{require, BridgeError};
+use crate::ext::sender;
 use pwasm_std::{types::U256, Box};
 
 pub struct Validateable(Box<dyn IBridgeValidators>);
 
 impl Validateable {
+    pub fn new(validator_contract: Box<dyn IBridgeValidators>) -> Self {
+        Validateable(validator_contract)
+    }
+
     pub fn validator_contract(&self) -> &dyn IBridgeValidators {
         &*self.0
     }
 
     pub fn check_validator(&self) {
-        assert!(
-            self.0.is_validator(sender()),
-            "This method can only be called by a validator"
-        )
-    }
-
-    pub fn check_owner(&self) {
-        assert_eq!(
-            self.0.owner(),
-            sender(),
-            "This method can only be called by the contract owner"
-        )
+        require(self.0.is_validator(sender()), BridgeError::NotValidator)
     }
 
     pub fn required_signatures(&self) -> usize {
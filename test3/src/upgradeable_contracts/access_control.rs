@@ -0,0 +1,215 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+//! Role-based access control.
+//!
+//! Roles live in eternal storage, so a proxy and the implementation it
+//! delegates to share a single set of roles: an account that is granted
+//! `UPGRADER_ROLE` through the bridge can upgrade the proxy, and vice versa.
+use super::super::upgradeability::eternal_storage::{key, EternalStorage};
+use crate::error::{require, BridgeError};
+use crate::ext::sender;
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::{
+    types::{Address, U256},
+    Vec,
+};
+
+include!(concat!(env!("OUT_DIR"), "/roles.rs"));
+
+/// The role that administers every role whose admin has not been changed,
+/// including itself.
+pub const DEFAULT_ADMIN_ROLE: [u8; 32] = [0; 32];
+
+/// Methods of the `AccessControl` endpoint.
+pub const ACCESS_CONTROL_METHODS: &[&str] = &[
+    "hasRole(bytes32,address)",
+    "getRoleAdmin(bytes32)",
+    "grantRole(bytes32,address)",
+    "revokeRole(bytes32,address)",
+    "renounceRole(bytes32,address)",
+    "setRoleAdmin(bytes32,bytes32)",
+];
+
+/// # AccessControl
+///
+/// Named roles, each of which is a set of accounts.  Every role has an admin
+/// role, whose members may grant and revoke it.
+#[eth_abi(AccessControl)]
+pub trait AccessControlTrait: EternalStorage {
+    /// Fired when `account` is granted `role` by `sender`.
+    #[event]
+    fn RoleGranted(
+        &mut self,
+        indexed_role: [u8; 32],
+        indexed_account: Address,
+        indexed_sender: Address,
+    );
+
+    /// Fired when `account` loses `role`.  `sender` is the account that
+    /// revoked the role, or `account` itself if it renounced it.
+    #[event]
+    fn RoleRevoked(
+        &mut self,
+        indexed_role: [u8; 32],
+        indexed_account: Address,
+        indexed_sender: Address,
+    );
+
+    /// Fired when the admin role of `role` changes.
+    #[event]
+    fn RoleAdminChanged(
+        &mut self,
+        indexed_role: [u8; 32],
+        indexed_previousAdminRole: [u8; 32],
+        indexed_newAdminRole: [u8; 32],
+    );
+
+    /// Tells whether `account` has `role`.
+    #[constant]
+    fn hasRole(&self, role: [u8; 32], account: Address) -> bool {
+        self.boolStorage(key(b"roles", &role_member(role, account)))
+    }
+
+    /// Tells the role whose members may grant and revoke `role`.
+    #[constant]
+    fn getRoleAdmin(&self, role: [u8; 32]) -> [u8; 32] {
+        self.uintStorage(key(b"roleAdmins", &role)).into()
+    }
+
+    /// Grants `role` to `account`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` does not have the admin role of `role`.
+    fn grantRole(&mut self, role: [u8; 32], account: Address) {
+        self.onlyRole(self.getRoleAdmin(role));
+        self.grant_role(role, account);
+    }
+
+    /// Revokes `role` from `account`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` does not have the admin role of `role`.
+    fn revokeRole(&mut self, role: [u8; 32], account: Address) {
+        self.onlyRole(self.getRoleAdmin(role));
+        self.revoke_role(role, account);
+    }
+
+    /// Gives up `role`.  `account` must be `msg.sender`; it is only a
+    /// parameter to guard against renouncing a role by mistake.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `account` is not `msg.sender`.
+    fn renounceRole(&mut self, role: [u8; 32], account: Address) {
+        require(account == sender(), BridgeError::RenounceForOther);
+        self.revoke_role(role, account);
+    }
+
+    /// Makes `adminRole` the admin role of `role`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` does not have the current admin role of
+    /// `role`.
+    fn setRoleAdmin(&mut self, role: [u8; 32], adminRole: [u8; 32]) {
+        self.onlyRole(self.getRoleAdmin(role));
+        self.set_role_admin(role, adminRole);
+    }
+}
+
+/// Internal helpers for [`AccessControlTrait`] that must not be part of the
+/// ABI.
+pub trait AccessControlInternal: AccessControlTrait {
+    /// Throws if `msg.sender` does not have `role`.
+    fn onlyRole(&self, role: [u8; 32]) {
+        require(self.hasRole(role, sender()), BridgeError::MissingRole)
+    }
+
+    /// Grants `role` to `account`, without checking `msg.sender`.
+    fn grant_role(&mut self, role: [u8; 32], account: Address) {
+        if !self.hasRole(role, account) {
+            self.setBoolStorage(key(b"roles", &role_member(role, account)), true);
+            self.RoleGranted(role, account, sender());
+        }
+    }
+
+    /// Revokes `role` from `account`, without checking `msg.sender`.
+    fn revoke_role(&mut self, role: [u8; 32], account: Address) {
+        if self.hasRole(role, account) {
+            self.setBoolStorage(key(b"roles", &role_member(role, account)), false);
+            self.RoleRevoked(role, account, sender());
+        }
+    }
+
+    /// Throws unless `msg.sender` may initialize the contract: an admin, or
+    /// an upgrader, who may initialize a new implementation with
+    /// `upgradeToAndCall`.  A proxy makes its deployer both.
+    ///
+    /// Without this, anyone could initialize a contract before its owner
+    /// did, and so become an admin of the roles that the proxy shares with
+    /// the contract.
+    fn onlyInitializer(&self) {
+        let sender = sender();
+        require(
+            self.hasRole(DEFAULT_ADMIN_ROLE, sender) || self.hasRole(UPGRADER_ROLE, sender),
+            BridgeError::MissingRole,
+        )
+    }
+
+    /// Makes `admin_role` the admin role of `role`, without checking
+    /// `msg.sender`.
+    fn set_role_admin(&mut self, role: [u8; 32], admin_role: [u8; 32]) {
+        let previous = self.getRoleAdmin(role);
+        self.setUintStorage(key(b"roleAdmins", &role), U256::from(admin_role));
+        self.RoleAdminChanged(role, previous, admin_role);
+    }
+
+    /// Grants `account` the admin role and every role in `roles`.  Called
+    /// when a contract is initialized.
+    fn setup_roles(&mut self, account: Address, roles: &[[u8; 32]]) {
+        require(account != Address::zero(), BridgeError::ZeroAddress);
+        self.grant_role(DEFAULT_ADMIN_ROLE, account);
+        for &role in roles {
+            self.grant_role(role, account);
+        }
+    }
+
+    /// Moves the admin role and every role in `roles` from
+    /// `previous_owner` to `new_owner`.  Called when ownership of a contract
+    /// changes hands, as the roles are what actually control it.
+    fn transfer_roles(&mut self, previous_owner: Address, new_owner: Address, roles: &[[u8; 32]]) {
+        for &role in roles.iter().chain(&[DEFAULT_ADMIN_ROLE]) {
+            self.grant_role(role, new_owner);
+            if previous_owner != new_owner {
+                self.revoke_role(role, previous_owner);
+            }
+        }
+    }
+}
+
+impl<T: AccessControlTrait + ?Sized> AccessControlInternal for T {}
+
+/// The key of the membership of `account` in `role`.
+fn role_member(role: [u8; 32], account: Address) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(52);
+    buf.extend_from_slice(&role);
+    buf.extend_from_slice(account.as_ref());
+    buf
+}
@@ -0,0 +1,178 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+#![forbid(warnings)]
+#![deny(unsafe_code)]
+use super::super::bridge_validators::ValidatorContract;
+use super::super::parse_message;
+use super::super::upgradeability::eternal_storage::{key, EternalStorage};
+use super::access_control::{
+    AccessControl, AccessControlInternal, AccessControlTrait, ACCESS_CONTROL_METHODS, PAUSER_ROLE,
+};
+use super::ownable::{Ownable, OwnableTrait, OwnerRoles, OWNABLE_METHODS};
+use super::pausable::{Pausable, PausableInternal, PausableTrait, PAUSABLE_METHODS};
+pub use super::Validateable::Validateable;
+use crate::dispatch::calls_any;
+use crate::error::{require, BridgeError};
+use pwasm_abi::eth::EndpointInterface;
+use pwasm_abi::types::{Address, Vec, U256};
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::{types::H256, Box};
+
+/// Methods of the `BasicForeignBridge` endpoint.
+pub const BASIC_FOREIGN_BRIDGE_METHODS: &[&str] = &[
+    "executeSignatures(bytes,bytes32[],bytes32[],bytes)",
+    "relayedMessages(bytes32)",
+    "validatorContract()",
+];
+
+/// The roles that the owner of a foreign bridge holds besides the admin
+/// role.
+pub const FOREIGN_BRIDGE_OWNER_ROLES: &[[u8; 32]] = &[PAUSER_ROLE];
+
+#[eth_abi(BasicForeignBridge)]
+pub trait BasicForeignBridgeInterface {
+    #[event]
+    fn RelayedMessage(&mut self, recipient: Address, value: U256, transactionHash: [u8; 32]);
+
+    /// Executes a message signed by enough validators on the home chain.
+    ///
+    /// `vs`, `rs`, and `ss` hold the components of the signatures, and
+    /// `message` is the message that was signed, in the format accepted by
+    /// `parse_message::parse_message`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following are true:
+    ///
+    /// * The message is not signed by enough distinct validators.
+    /// * The message is addressed to a different contract.
+    /// * The bridge is paused.
+    /// * The message has already been relayed.
+    fn executeSignatures(
+        &mut self,
+        vs: Vec<u8>,
+        rs: Vec<[u8; 32]>,
+        ss: Vec<[u8; 32]>,
+        message: Vec<u8>,
+    );
+
+    #[constant]
+    fn relayedMessages(&self, transactionHash: [u8; 32]) -> bool;
+
+    #[constant]
+    fn validatorContract(&self) -> Address;
+}
+
+/// The part of a foreign bridge that depends on what is being bridged.
+pub trait ForeignBridgeMode {
+    /// Delivers `amount` to `recipient`.  Returns `true` on success.
+    fn onExecuteMessage(&mut self, recipient: Address, amount: U256) -> bool;
+}
+
+pub struct BasicForeignBridgeContract<M: ForeignBridgeMode>(Validateable, M);
+
+impl<M: ForeignBridgeMode> EternalStorage for BasicForeignBridgeContract<M> {}
+
+impl<M: ForeignBridgeMode> OwnableTrait for BasicForeignBridgeContract<M> {}
+
+impl<M: ForeignBridgeMode> OwnerRoles for BasicForeignBridgeContract<M> {
+    fn transfer_owner_roles(&mut self, previous_owner: Address, new_owner: Address) {
+        self.transfer_roles(previous_owner, new_owner, FOREIGN_BRIDGE_OWNER_ROLES)
+    }
+}
+
+impl<M: ForeignBridgeMode> AccessControlTrait for BasicForeignBridgeContract<M> {}
+
+impl<M: ForeignBridgeMode> PausableTrait for BasicForeignBridgeContract<M> {}
+
+/// Dispatches `input` to the endpoints that every foreign bridge implements.
+/// If `input` is not a call to any of them, the contract is handed back so
+/// that the caller can dispatch it to the endpoints of its bridge mode.
+pub fn dispatch<M: ForeignBridgeMode>(
+    contract: BasicForeignBridgeContract<M>,
+    input: &[u8],
+) -> Result<Vec<u8>, BasicForeignBridgeContract<M>> {
+    if calls_any(input, BASIC_FOREIGN_BRIDGE_METHODS) {
+        Ok(BasicForeignBridge::new(contract).dispatch(input))
+    } else if calls_any(input, PAUSABLE_METHODS) {
+        Ok(Pausable::new(contract).dispatch(input))
+    } else if calls_any(input, ACCESS_CONTROL_METHODS) {
+        Ok(AccessControl::new(contract).dispatch(input))
+    } else if calls_any(input, OWNABLE_METHODS) {
+        Ok(Ownable::new(contract).dispatch(input))
+    } else {
+        Err(contract)
+    }
+}
+
+impl<M: ForeignBridgeMode> BasicForeignBridgeContract<M> {
+    /// Creates a foreign bridge that uses the validator contract stored in
+    /// eternal storage.
+    pub fn new(mode: M) -> Self {
+        BasicForeignBridgeContract(Validateable::new(Box::new(ValidatorContract)), mode)
+    }
+
+    fn setRelayedMessages(&mut self, transaction_hash: [u8; 32], status: bool) {
+        self.setBoolStorage(key(b"relayedMessages", &transaction_hash), status)
+    }
+}
+
+impl<M: ForeignBridgeMode> BasicForeignBridgeInterface for BasicForeignBridgeContract<M> {
+    fn executeSignatures(
+        &mut self,
+        vs: Vec<u8>,
+        rs: Vec<[u8; 32]>,
+        ss: Vec<[u8; 32]>,
+        message: Vec<u8>,
+    ) {
+        self.whenNotPaused();
+        let rs: Vec<H256> = rs.into_iter().map(H256::from).collect();
+        let ss: Vec<H256> = ss.into_iter().map(H256::from).collect();
+        parse_message::has_enough_valid_signatures(
+            &message,
+            &vs,
+            &rs,
+            &ss,
+            self.0.validator_contract(),
+        );
+        let parsed = parse_message::parse_message(&message);
+        require(
+            parsed.contract_address == crate::ext::address(),
+            BridgeError::WrongContract,
+        );
+        let transaction_hash: [u8; 32] = parsed.tx_hash.into();
+        require(
+            !self.relayedMessages(transaction_hash),
+            BridgeError::AlreadyRelayed,
+        );
+        self.setRelayedMessages(transaction_hash, true);
+        require(
+            self.1.onExecuteMessage(parsed.recipient, parsed.amount),
+            BridgeError::ExecutionFailed,
+        );
+        self.RelayedMessage(parsed.recipient, parsed.amount, transaction_hash);
+    }
+
+    fn relayedMessages(&self, transaction_hash: [u8; 32]) -> bool {
+        self.boolStorage(key(b"relayedMessages", &transaction_hash))
+    }
+
+    fn validatorContract(&self) -> Address {
+        ValidatorContract.address()
+    }
+}
@@ -21,11 +21,43 @@ extern crate pwasm_abi;
 extern crate pwasm_abi_derive;
 extern crate pwasm_ethereum;
 extern crate pwasm_std;
+use super::super::bridge_validators::ValidatorContract;
 use super::super::parse_message;
+use super::super::upgradeability::eternal_storage::{key, EternalStorage};
+use super::access_control::{
+    AccessControl, AccessControlInternal, AccessControlTrait, ACCESS_CONTROL_METHODS,
+    LIMITS_MANAGER_ROLE, PAUSER_ROLE,
+};
+use super::bridge_limits::{BridgeLimitsInternal, BridgeLimitsTrait, BRIDGE_LIMITS_METHODS};
+use super::fee_manager::{FeeManagerInternal, FeeManagerTrait, FeeType, FEE_MANAGER_METHODS};
+use super::ownable::{Ownable, OwnableTrait, OwnerRoles, OWNABLE_METHODS};
+use super::pausable::{Pausable, PausableInternal, PausableTrait, PAUSABLE_METHODS};
 pub use super::Validateable::Validateable;
+use crate::dispatch::calls_any;
+use crate::error::{require, revert, BridgeError};
+use pwasm_abi::eth::EndpointInterface;
 use pwasm_abi::types::{Address, Vec, U256};
 use pwasm_abi_derive::eth_abi;
-use pwasm_std::keccak;
+use pwasm_std::{keccak, Box};
+
+/// Methods of the `BasicHomeBridge` endpoint.
+pub const BASIC_HOME_BRIDGE_METHODS: &[&str] = &[
+    "executeAffirmation(address,uint256,bytes32)",
+    "submitSignature(bytes,bytes)",
+    "numAffirmationsSigned(bytes32)",
+    "affirmationsSigned(bytes32)",
+    "signature(bytes32,uint256)",
+    "messagesSigned(bytes32)",
+    "message(bytes32)",
+    "isAlreadyProcessed(uint256)",
+    "numMessagesSigned(bytes32)",
+    "requiredMessageLength()",
+    "validatorContract()",
+    "withheldHomeFees()",
+];
+
+/// The roles that the owner of a home bridge holds besides the admin role.
+pub const HOME_BRIDGE_OWNER_ROLES: &[[u8; 32]] = &[LIMITS_MANAGER_ROLE, PAUSER_ROLE];
 
 #[eth_abi(BasicHomeBridge)]
 pub trait BasicHomeBridgeInterface {
@@ -75,56 +107,210 @@ pub trait BasicHomeBridgeInterface {
 
     #[constant]
     fn requiredMessageLength(&self) -> U256;
+
+    #[constant]
+    fn validatorContract(&self) -> Address;
+
+    /// Returns the home fees that have been withheld from requests and not
+    /// yet paid out to the validators.
+    #[constant]
+    fn withheldHomeFees(&self) -> U256;
+}
+
+/// The part of a home bridge that depends on what is being bridged.
+pub trait HomeBridgeMode {
+    /// Delivers `value` to `recipient`.  Returns `true` on success.
+    fn onExecuteAffirmation(&mut self, recipient: Address, value: U256) -> bool;
+}
+
+pub struct BasicHomeBridgeContract<M: HomeBridgeMode>(Validateable, M);
+
+impl<M: HomeBridgeMode> EternalStorage for BasicHomeBridgeContract<M> {}
+
+impl<M: HomeBridgeMode> OwnableTrait for BasicHomeBridgeContract<M> {}
+
+impl<M: HomeBridgeMode> OwnerRoles for BasicHomeBridgeContract<M> {
+    fn transfer_owner_roles(&mut self, previous_owner: Address, new_owner: Address) {
+        self.transfer_roles(previous_owner, new_owner, HOME_BRIDGE_OWNER_ROLES)
+    }
+}
+
+impl<M: HomeBridgeMode> AccessControlTrait for BasicHomeBridgeContract<M> {}
+
+impl<M: HomeBridgeMode> BridgeLimitsTrait for BasicHomeBridgeContract<M> {}
+
+impl<M: HomeBridgeMode> FeeManagerTrait for BasicHomeBridgeContract<M> {}
+
+impl<M: HomeBridgeMode> PausableTrait for BasicHomeBridgeContract<M> {}
+
+/// Dispatches `input` to the endpoints that every home bridge implements.
+/// If `input` is not a call to any of them, the contract is handed back so
+/// that the caller can dispatch it to the endpoints of its bridge mode.
+pub fn dispatch<M: HomeBridgeMode>(
+    contract: BasicHomeBridgeContract<M>,
+    input: &[u8],
+) -> Result<Vec<u8>, BasicHomeBridgeContract<M>> {
+    if calls_any(input, BASIC_HOME_BRIDGE_METHODS) {
+        Ok(BasicHomeBridge::new(contract).dispatch(input))
+    } else if calls_any(input, BRIDGE_LIMITS_METHODS) {
+        Ok(BridgeLimits::new(contract).dispatch(input))
+    } else if calls_any(input, FEE_MANAGER_METHODS) {
+        Ok(FeeManager::new(contract).dispatch(input))
+    } else if calls_any(input, PAUSABLE_METHODS) {
+        Ok(Pausable::new(contract).dispatch(input))
+    } else if calls_any(input, ACCESS_CONTROL_METHODS) {
+        Ok(AccessControl::new(contract).dispatch(input))
+    } else if calls_any(input, OWNABLE_METHODS) {
+        Ok(Ownable::new(contract).dispatch(input))
+    } else {
+        Err(contract)
+    }
 }
 
-pub struct BasicHomeBridgeContract(Validateable);
+impl<M: HomeBridgeMode> BasicHomeBridgeContract<M> {
+    /// Creates a home bridge that uses the validator contract stored in
+    /// eternal storage.
+    pub fn new(mode: M) -> Self {
+        BasicHomeBridgeContract(Validateable::new(Box::new(ValidatorContract)), mode)
+    }
+
+    /// Requests that `value`, less the home fee, be transferred to
+    /// `recipient` on the foreign chain.  The validators pick up the
+    /// `UserRequestForSignature` event and sign the transfer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bridge is paused, if `value` is zero or is not within
+    /// the limits, or if a home fee is set but the validator contract lists
+    /// no validators to pay it to.
+    pub fn requestSignature(&mut self, recipient: Address, value: U256) {
+        self.whenNotPaused();
+        require(!value.is_zero(), BridgeError::ZeroValue);
+        self.spend(value);
+        let fee = self.calculate_fee(value, FeeType::Home);
+        // The fee is only paid out once the signatures are collected, and
+        // the transfer cannot be refunded, so it must be payable now.
+        require(
+            fee.is_zero() || !self.0.validator_contract().validator_list().is_empty(),
+            BridgeError::NoFeeRecipients,
+        );
+        self.withhold_home_fee(recipient, value - fee, fee);
+        self.UserRequestForSignature(recipient, value - fee);
+    }
+
+    /// The key of the fees withheld from requests to transfer `amount` to
+    /// `recipient`.  The signed message names only the recipient and the
+    /// amount left after the fee, so that is all a fee can be found by.
+    fn withheld_home_fee_key(recipient: Address, amount: U256) -> [u8; 52] {
+        let mut buf = [0; 52];
+        buf[..20].copy_from_slice(recipient.as_ref());
+        buf[20..].copy_from_slice(&<[u8; 32]>::from(amount));
+        buf
+    }
+
+    /// Records that `fee` was withheld from a request to transfer `amount`
+    /// to `recipient`.
+    fn withhold_home_fee(&mut self, recipient: Address, amount: U256, fee: U256) {
+        let buf = Self::withheld_home_fee_key(recipient, amount);
+        let fees = self.uintStorage(key(b"withheldHomeFee", &buf));
+        let requests = self.uintStorage(key(b"homeFeeRequests", &buf));
+        self.setUintStorage(key(b"withheldHomeFee", &buf), fees + fee);
+        self.setUintStorage(key(b"homeFeeRequests", &buf), requests + U256::from(1));
+        let total = self.withheldHomeFees();
+        self.setUintStorage(key(b"withheldHomeFees", &[]), total + fee);
+    }
+
+    /// Takes the fee withheld from a request to transfer `amount` to
+    /// `recipient` out of the withheld fees, and returns it.  If several such
+    /// requests were made with different fees, each is paid the average.
+    /// Requests made before fees were recorded have no fee to pay out.
+    fn release_home_fee(&mut self, recipient: Address, amount: U256) -> U256 {
+        let buf = Self::withheld_home_fee_key(recipient, amount);
+        let requests = self.uintStorage(key(b"homeFeeRequests", &buf));
+        if requests.is_zero() {
+            return U256::zero();
+        }
+        let fees = self.uintStorage(key(b"withheldHomeFee", &buf));
+        let fee = fees / requests;
+        self.setUintStorage(key(b"withheldHomeFee", &buf), fees - fee);
+        self.setUintStorage(key(b"homeFeeRequests", &buf), requests - U256::from(1));
+        let total = self.withheldHomeFees();
+        self.setUintStorage(key(b"withheldHomeFees", &[]), total - fee);
+        fee
+    }
+
+    /// Pays `fee` out to the validators, using the bridge mode to deliver
+    /// each share, and fires `FeeDistributed`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fee` is not zero and the validator contract lists no
+    /// validators to pay it to.
+    fn distributeFee(&mut self, fee: U256, transaction_hash: [u8; 32]) {
+        if fee.is_zero() {
+            return;
+        }
+        let shares = self.distribute_fee(fee, self.0.validator_contract());
+        require(!shares.is_empty(), BridgeError::NoFeeRecipients);
+        for (reward_address, amount) in shares {
+            require(
+                self.onExecuteAffirmation(reward_address, amount),
+                BridgeError::ExecutionFailed,
+            );
+        }
+        self.FeeDistributed(fee, transaction_hash);
+    }
 
-impl BasicHomeBridgeContract {
-    fn setNumMessagesSigned(&mut self, _message: [u8; 32], _number: U256) {
-        unimplemented!()
+    fn setNumMessagesSigned(&mut self, message: [u8; 32], number: U256) {
+        self.setUintStorage(key(b"numMessagesSigned", &message), number)
     }
 
     fn markAsProcessed(&self, v: U256) -> U256 {
         v | U256::from(1) << 255
     }
 
-    fn messages(&self, _hash: [u8; 32]) -> Vec<u8> {
-        unimplemented!()
+    fn messages(&self, hash: [u8; 32]) -> Vec<u8> {
+        self.bytesStorage(key(b"messages", &hash))
     }
 
-    fn signatures(&self, _hash: [u8; 32]) -> Vec<u8> {
-        unimplemented!()
+    fn signatures(&self, hash: [u8; 32]) -> Vec<u8> {
+        self.bytesStorage(key(b"signatures", &hash))
     }
 
-    fn setSignatures(&mut self, _hash: [u8; 32], _signature: Vec<u8>) {
-        unimplemented!()
+    fn setSignatures(&mut self, hash: [u8; 32], signature: Vec<u8>) {
+        self.setBytesStorage(key(b"signatures", &hash), &signature)
     }
 
-    fn setMessages(&mut self, _hash: [u8; 32], _message: Vec<u8>) {
-        unimplemented!()
+    fn setMessages(&mut self, hash: [u8; 32], message: Vec<u8>) {
+        self.setBytesStorage(key(b"messages", &hash), &message)
     }
 
-    fn setAffirmationsSigned(&mut self, _withdrawl: [u8; 32], _status: bool) {
-        unimplemented!()
+    fn setAffirmationsSigned(&mut self, withdrawal: [u8; 32], status: bool) {
+        self.setBoolStorage(key(b"affirmationsSigned", &withdrawal), status)
     }
 
-    fn setNumAffirmationsSigned(&mut self, _withdrawal: [u8; 32], _number: U256) {
-        unimplemented!()
+    fn setNumAffirmationsSigned(&mut self, withdrawal: [u8; 32], number: U256) {
+        self.setUintStorage(key(b"numAffirmationsSigned", &withdrawal), number)
     }
 
-    fn setMessagesSigned(&mut self, _hash: [u8; 32], _status: bool) {
-        panic!("Don’t know how to store data persistently");
+    fn setMessagesSigned(&mut self, hash: [u8; 32], status: bool) {
+        self.setBoolStorage(key(b"messagesSigned", &hash), status)
     }
 
-    fn onExecuteAffirmation(&mut self, _address: Address, _value: U256) -> bool {
-        unimplemented!()
+    fn onExecuteAffirmation(&mut self, recipient: Address, value: U256) -> bool {
+        self.1.onExecuteAffirmation(recipient, value)
     }
 }
 
-impl BasicHomeBridgeInterface for BasicHomeBridgeContract {
+impl<M: HomeBridgeMode> BasicHomeBridgeInterface for BasicHomeBridgeContract<M> {
     #[allow(unsafe_code)]
     fn executeAffirmation(&mut self, recipient: Address, value: U256, transaction_hash: [u8; 32]) {
+        self.whenNotPaused();
         self.0.check_validator();
+        require(
+            self.withinExecutionLimit(value),
+            BridgeError::ExceedsExecutionLimits,
+        );
         let (hash_msg, hash_sender) = {
             let mut buf = Vec::with_capacity(84);
 
@@ -139,48 +325,64 @@ impl BasicHomeBridgeInterface for BasicHomeBridgeContract {
             debug_assert_eq!(buf.len(), 84);
             let hash_msg: [u8; 32] = keccak(&buf).into();
             unsafe { buf.set_len(0) };
-            let sender: Address = pwasm_ethereum::sender();
+            let sender: Address = crate::ext::sender();
             buf.extend_from_slice(sender.as_ref());
             buf.extend_from_slice(hash_msg.as_ref());
             (hash_msg, keccak(&buf).into())
         };
 
-        assert!(!self.affirmationsSigned(hash_sender));
+        require(
+            !self.affirmationsSigned(hash_sender),
+            BridgeError::DuplicateSignature,
+        );
         self.setAffirmationsSigned(hash_sender, true);
 
         let mut signed: U256 = self.numAffirmationsSigned(hash_msg);
 
-        assert!(!self.isAlreadyProcessed(signed));
+        require(
+            !self.isAlreadyProcessed(signed),
+            BridgeError::AlreadyProcessed,
+        );
 
         signed += 1.into();
 
         self.setNumAffirmationsSigned(hash_msg, signed);
 
-        self.SignedForAffirmation(pwasm_ethereum::sender(), transaction_hash);
+        self.SignedForAffirmation(crate::ext::sender(), transaction_hash);
 
         if signed >= self.0.required_signatures().into() {
             // If the bridge contract does not own enough tokens to transfer
             // it will couse funds lock on the home side of the bridge
             self.setNumAffirmationsSigned(hash_msg, self.markAsProcessed(signed));
-            assert!(self.onExecuteAffirmation(recipient, value));
+            self.execute(value);
+            let mut fee = self.calculate_fee(value, FeeType::Foreign);
+            if self.0.validator_contract().validator_list().is_empty() {
+                // There is no one to pay the fee to, so none is charged.
+                fee = U256::zero();
+            }
+            require(
+                self.onExecuteAffirmation(recipient, value - fee),
+                BridgeError::ExecutionFailed,
+            );
+            self.distributeFee(fee, transaction_hash);
             self.AffirmationCompleted(recipient, value, transaction_hash);
         }
     }
 
     fn submitSignature(&mut self, signature: Vec<u8>, message: Vec<u8>) {
+        self.whenNotPaused();
         // check that the sender is a validator
         self.0.check_validator();
 
-        let sender = pwasm_ethereum::sender();
+        let sender = crate::ext::sender();
 
         // ensure that `signature` is really `message` signed by `msg.sender`
         // `parse_message::recover_address_from_signed_message` also validates
         // the signature and message and ensures both are valid.
-        assert_eq!(
-            sender,
-            parse_message::recover_address_from_signed_message(&signature, &message),
-            "Message not signed by sender"
-        );
+        let signer = parse_message::recover_address_from_signed_message(&signature, &message)
+            .unwrap_or_else(|| revert(BridgeError::InvalidSignature));
+        require(signer == sender, BridgeError::MessageNotSignedBySender);
+        let parsed = parse_message::parse_message(&message);
         let hash_msg: [u8; 32] = keccak(&message).into();
         let hash_sender = {
             let mut q = [0; 52];
@@ -190,12 +392,18 @@ impl BasicHomeBridgeInterface for BasicHomeBridgeContract {
         }
         .into();
         let mut signed = self.numMessagesSigned(hash_msg);
-        assert!(!self.isAlreadyProcessed(signed));
+        require(
+            !self.isAlreadyProcessed(signed),
+            BridgeError::AlreadyProcessed,
+        );
         signed += 1.into();
         // the check above assures that the case when the value could be overflew will not happen in the addition operation below
         if signed > 1.into() {
             // Duplicated signatures
-            assert!(!self.messagesSigned(hash_sender));
+            require(
+                !self.messagesSigned(hash_sender),
+                BridgeError::DuplicateSignature,
+            );
         } else {
             self.setMessages(hash_msg, message);
         }
@@ -218,34 +426,34 @@ impl BasicHomeBridgeInterface for BasicHomeBridgeContract {
         if signed >= req_sigs {
             self.setNumMessagesSigned(hash_msg, self.markAsProcessed(signed));
             self.CollectedSignatures(sender, hash_msg, req_sigs);
+            // The fee was withheld when the user made the request, and the
+            // home fee may have changed since, so pay out what was withheld.
+            let fee = self.release_home_fee(parsed.recipient, parsed.amount);
+            self.distributeFee(fee, hash_msg);
         }
     }
 
-    fn numAffirmationsSigned(&self, _withdrawl: [u8; 32]) -> U256 {
-        unimplemented!();
+    fn numAffirmationsSigned(&self, withdrawal: [u8; 32]) -> U256 {
+        self.uintStorage(key(b"numAffirmationsSigned", &withdrawal))
     }
 
-    fn affirmationsSigned(&self, _withdrawal: [u8; 32]) -> bool {
-        unimplemented!();
+    fn affirmationsSigned(&self, withdrawal: [u8; 32]) -> bool {
+        self.boolStorage(key(b"affirmationsSigned", &withdrawal))
     }
 
-    #[allow(unsafe_code)]
     fn signature(&self, hash: [u8; 32], index: U256) -> Vec<u8> {
-        // FIXME is this unsafe approach worth it??????
-        let signIdx = unsafe {
-            let mut q: [u8; 64] = core::mem::uninitialized();
-            core::ptr::copy_nonoverlapping(hash.as_ptr(), q.as_mut_ptr(), 32);
-            index.to_little_endian(core::slice::from_raw_parts_mut(
-                q.as_mut_ptr().offset(32),
-                32,
-            ));
+        // Must match the index computed in `submitSignature`
+        let signIdx = {
+            let mut q = [0; 64];
+            q[..32].copy_from_slice(&hash);
+            q[32..].copy_from_slice(&<[u8; 32]>::from(index));
             keccak(&q[..]).into()
         };
         self.signatures(signIdx)
     }
 
-    fn messagesSigned(&self, _message: [u8; 32]) -> bool {
-        unimplemented!();
+    fn messagesSigned(&self, message: [u8; 32]) -> bool {
+        self.boolStorage(key(b"messagesSigned", &message))
     }
 
     fn message(&self, hash: [u8; 32]) -> Vec<u8> {
@@ -258,11 +466,248 @@ impl BasicHomeBridgeInterface for BasicHomeBridgeContract {
         (q[31] & 0x80) != 0
     }
 
-    fn numMessagesSigned(&self, _message: [u8; 32]) -> U256 {
-        unimplemented!();
+    fn numMessagesSigned(&self, message: [u8; 32]) -> U256 {
+        self.uintStorage(key(b"numMessagesSigned", &message))
     }
 
     fn requiredMessageLength(&self) -> U256 {
         parse_message::REQUIRED_MESSAGE_LENGTH.into()
     }
+
+    fn validatorContract(&self) -> Address {
+        ValidatorContract.address()
+    }
+
+    fn withheldHomeFees(&self) -> U256 {
+        self.uintStorage(key(b"withheldHomeFees", &[]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge_validators::IBridgeValidators;
+    use crate::ext::native;
+    use crate::simulator::bridge::Validator;
+    use crate::simulator::event_topic;
+
+    /// A validator contract that lists a fixed set of validators.
+    struct Validators {
+        validators: Vec<Address>,
+        required: usize,
+    }
+
+    impl IBridgeValidators for Validators {
+        fn is_validator(&self, validator: Address) -> bool {
+            self.validators.contains(&validator)
+        }
+
+        fn required_signatures(&self) -> usize {
+            self.required
+        }
+
+        fn owner(&self) -> Address {
+            Address::zero()
+        }
+
+        fn validator_list(&self) -> Vec<Address> {
+            self.validators.clone()
+        }
+
+        fn reward_address(&self, _validator: Address) -> Address {
+            Address::zero()
+        }
+    }
+
+    /// A bridge mode that records what it delivers.
+    #[derive(Default)]
+    struct Deliveries(Vec<(Address, U256)>);
+
+    impl HomeBridgeMode for Deliveries {
+        fn onExecuteAffirmation(&mut self, recipient: Address, value: U256) -> bool {
+            self.0.push((recipient, value));
+            true
+        }
+    }
+
+    fn delivered(bridge: &BasicHomeBridgeContract<Deliveries>) -> &[(Address, U256)] {
+        &(bridge.1).0
+    }
+
+    /// A home bridge with three validators, two of whom must sign each
+    /// transfer.
+    fn bridge() -> (BasicHomeBridgeContract<Deliveries>, Vec<Validator>) {
+        native::reset();
+        let validators: Vec<_> = (0..3).map(Validator::new).collect();
+        let mut bridge = BasicHomeBridgeContract(
+            Validateable::new(Box::new(Validators {
+                validators: validators.iter().map(|v| v.address).collect(),
+                required: 2,
+            })),
+            Deliveries::default(),
+        );
+        bridge.setUintStorage(key(b"executionDailyLimit", &[]), 1000.into());
+        bridge.setUintStorage(key(b"executionMaxPerTx", &[]), 100.into());
+        (bridge, validators)
+    }
+
+    fn recipient() -> Address {
+        Address::from([0x77; 20])
+    }
+
+    fn as_sender(validator: &Validator) {
+        native::with(|ext| ext.sender = validator.address);
+    }
+
+    fn reverts_with<R>(f: impl FnOnce() -> R) -> Option<BridgeError> {
+        BridgeError::decode(&native::catch_revert(f).err()?)
+    }
+
+    fn emitted(event: &str) -> usize {
+        let topic = event_topic(event);
+        native::with(|ext| ext.logs.iter().filter(|log| log.topics[0] == topic).count())
+    }
+
+    #[test]
+    fn executes_affirmation_at_quorum() {
+        let (mut bridge, validators) = bridge();
+        let transaction_hash = [0x42; 32];
+        as_sender(&validators[0]);
+        bridge.executeAffirmation(recipient(), 10.into(), transaction_hash);
+        assert!(delivered(&bridge).is_empty());
+        assert_eq!(
+            reverts_with(|| bridge.executeAffirmation(recipient(), 10.into(), transaction_hash)),
+            Some(BridgeError::DuplicateSignature)
+        );
+        native::with(|ext| ext.sender = recipient());
+        assert_eq!(
+            reverts_with(|| bridge.executeAffirmation(recipient(), 10.into(), transaction_hash)),
+            Some(BridgeError::NotValidator)
+        );
+
+        as_sender(&validators[1]);
+        bridge.executeAffirmation(recipient(), 10.into(), transaction_hash);
+        assert_eq!(delivered(&bridge), &[(recipient(), 10.into())]);
+        assert_eq!(emitted("AffirmationCompleted(address,uint256,bytes32)"), 1);
+        as_sender(&validators[2]);
+        assert_eq!(
+            reverts_with(|| bridge.executeAffirmation(recipient(), 10.into(), transaction_hash)),
+            Some(BridgeError::AlreadyProcessed)
+        );
+        assert_eq!(delivered(&bridge).len(), 1);
+    }
+
+    #[test]
+    fn rejects_affirmation_over_limit() {
+        let (mut bridge, validators) = bridge();
+        as_sender(&validators[0]);
+        assert_eq!(
+            reverts_with(|| bridge.executeAffirmation(recipient(), 101.into(), [0x42; 32])),
+            Some(BridgeError::ExceedsExecutionLimits)
+        );
+    }
+
+    /// The message that the validators sign for a transfer of `amount` to
+    /// `recipient`.
+    fn message(amount: U256) -> Vec<u8> {
+        let mut message = recipient()[..].to_vec();
+        let mut buf = [0; 32];
+        amount.to_little_endian(&mut buf);
+        message.extend_from_slice(&buf);
+        message.extend_from_slice(&[0x42; 32]);
+        message.extend_from_slice(&[0x99; 20]);
+        message
+    }
+
+    #[test]
+    fn collects_signatures_at_quorum() {
+        let (mut bridge, validators) = bridge();
+        let message = message(10.into());
+        let hash: [u8; 32] = keccak(&message).into();
+        let signatures: Vec<_> = validators.iter().map(|v| v.sign(&message)).collect();
+
+        as_sender(&validators[0]);
+        bridge.submitSignature(signatures[0].clone(), message.clone());
+        assert_eq!(bridge.numMessagesSigned(hash), 1.into());
+        assert_eq!(
+            reverts_with(|| bridge.submitSignature(signatures[0].clone(), message.clone())),
+            Some(BridgeError::DuplicateSignature)
+        );
+        assert_eq!(
+            reverts_with(|| bridge.submitSignature(signatures[1].clone(), message.clone())),
+            Some(BridgeError::MessageNotSignedBySender)
+        );
+        let mut malformed = signatures[0].clone();
+        malformed[64] = 0;
+        assert_eq!(
+            reverts_with(|| bridge.submitSignature(malformed, message.clone())),
+            Some(BridgeError::InvalidSignature)
+        );
+        assert_eq!(emitted("CollectedSignatures(address,bytes32,uint256)"), 0);
+
+        as_sender(&validators[1]);
+        bridge.submitSignature(signatures[1].clone(), message.clone());
+        assert!(bridge.isAlreadyProcessed(bridge.numMessagesSigned(hash)));
+        assert_eq!(emitted("CollectedSignatures(address,bytes32,uint256)"), 1);
+        assert_eq!(bridge.message(hash), message);
+        assert_eq!(bridge.signature(hash, 0.into()), signatures[0]);
+        assert_eq!(bridge.signature(hash, 1.into()), signatures[1]);
+        assert!(bridge.messagesSigned(signed_key(&validators[1], hash)));
+
+        as_sender(&validators[2]);
+        assert_eq!(
+            reverts_with(|| bridge.submitSignature(signatures[2].clone(), message.clone())),
+            Some(BridgeError::AlreadyProcessed)
+        );
+    }
+
+    #[test]
+    fn pays_out_home_fee_withheld_at_request() {
+        let (mut bridge, validators) = bridge();
+        bridge.setUintStorage(key(b"dailyLimit", &[]), 1000.into());
+        bridge.setUintStorage(key(b"maxPerTx", &[]), 100.into());
+        // 10%, so 9 of 99 is withheld, and 90 is transferred.
+        bridge.setUintStorage(key(b"homeFee", &[]), 100_000_000_000_000_000u64.into());
+        bridge.requestSignature(recipient(), 99.into());
+        assert_eq!(bridge.withheldHomeFees(), 9.into());
+
+        // Raising the fee to 50% must not change what is paid out.
+        bridge.setUintStorage(key(b"homeFee", &[]), 500_000_000_000_000_000u64.into());
+        let message = message(90.into());
+        for validator in &validators[..2] {
+            as_sender(validator);
+            bridge.submitSignature(validator.sign(&message), message.clone());
+        }
+        assert_eq!(
+            delivered(&bridge),
+            &[
+                (validators[0].address, 3.into()),
+                (validators[1].address, 3.into()),
+                (validators[2].address, 3.into()),
+            ]
+        );
+        assert_eq!(bridge.withheldHomeFees(), 0.into());
+        assert_eq!(emitted("FeeDistributed(uint256,bytes32)"), 1);
+    }
+
+    #[test]
+    fn pays_no_home_fee_for_unrecorded_request() {
+        let (mut bridge, validators) = bridge();
+        bridge.setUintStorage(key(b"homeFee", &[]), 500_000_000_000_000_000u64.into());
+        let message = message(90.into());
+        for validator in &validators[..2] {
+            as_sender(validator);
+            bridge.submitSignature(validator.sign(&message), message.clone());
+        }
+        assert!(delivered(&bridge).is_empty());
+        assert_eq!(emitted("CollectedSignatures(address,bytes32,uint256)"), 1);
+    }
+
+    /// The key under which `submitSignature` records that `validator`
+    /// signed the message with hash `hash`.
+    fn signed_key(validator: &Validator, hash: [u8; 32]) -> [u8; 32] {
+        let mut buf = validator.address[..].to_vec();
+        buf.extend_from_slice(&hash);
+        keccak(&buf).into()
+    }
 }
@@ -0,0 +1,359 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+use super::access_control::{AccessControlInternal, AccessControlTrait, LIMITS_MANAGER_ROLE};
+use crate::error::{require, BridgeError};
+use crate::ext::timestamp;
+use crate::upgradeability::eternal_storage::key;
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::types::U256;
+
+/// Methods of the `BridgeLimits` endpoint.
+pub const BRIDGE_LIMITS_METHODS: &[&str] = &[
+    "dailyLimit()",
+    "maxPerTx()",
+    "minPerTx()",
+    "executionDailyLimit()",
+    "executionMaxPerTx()",
+    "totalSpentPerDay(uint256)",
+    "totalExecutedPerDay(uint256)",
+    "getCurrentDay()",
+    "withinLimit(uint256)",
+    "withinExecutionLimit(uint256)",
+    "setDailyLimit(uint256)",
+    "setMaxPerTx(uint256)",
+    "setMinPerTx(uint256)",
+    "setExecutionDailyLimit(uint256)",
+    "setExecutionMaxPerTx(uint256)",
+];
+
+/// One day, in seconds.
+const DAY: u64 = 24 * 60 * 60;
+
+/// # BridgeLimits
+///
+/// Limits on the value a bridge moves.  Outgoing transfers (user requests)
+/// are limited by `dailyLimit`, `maxPerTx`, and `minPerTx`; incoming
+/// transfers (executed affirmations) by `executionDailyLimit` and
+/// `executionMaxPerTx`.  Days are numbered `block timestamp / 1 day`.
+///
+/// All limits start at zero, which blocks all transfers, so a limits manager
+/// must set them before the bridge can be used.  Either direction can be
+/// stopped again by setting its daily limit to zero; a per-transaction
+/// maximum is never zero, and always lies strictly between the minimum (zero
+/// for executions) and the daily limit.
+#[eth_abi(BridgeLimits)]
+pub trait BridgeLimitsTrait: AccessControlTrait {
+    #[event]
+    fn DailyLimitChanged(&mut self, newLimit: U256);
+
+    #[event]
+    fn MaxPerTxChanged(&mut self, newLimit: U256);
+
+    #[event]
+    fn MinPerTxChanged(&mut self, newLimit: U256);
+
+    #[event]
+    fn ExecutionDailyLimitChanged(&mut self, newLimit: U256);
+
+    #[event]
+    fn ExecutionMaxPerTxChanged(&mut self, newLimit: U256);
+
+    #[constant]
+    fn dailyLimit(&self) -> U256 {
+        self.uintStorage(key(b"dailyLimit", &[]))
+    }
+
+    #[constant]
+    fn maxPerTx(&self) -> U256 {
+        self.uintStorage(key(b"maxPerTx", &[]))
+    }
+
+    #[constant]
+    fn minPerTx(&self) -> U256 {
+        self.uintStorage(key(b"minPerTx", &[]))
+    }
+
+    #[constant]
+    fn executionDailyLimit(&self) -> U256 {
+        self.uintStorage(key(b"executionDailyLimit", &[]))
+    }
+
+    #[constant]
+    fn executionMaxPerTx(&self) -> U256 {
+        self.uintStorage(key(b"executionMaxPerTx", &[]))
+    }
+
+    /// The total value of user requests made on `day`.
+    #[constant]
+    fn totalSpentPerDay(&self, day: U256) -> U256 {
+        self.uintStorage(key(b"totalSpentPerDay", &<[u8; 32]>::from(day)))
+    }
+
+    /// The total value of affirmations executed on `day`.
+    #[constant]
+    fn totalExecutedPerDay(&self, day: U256) -> U256 {
+        self.uintStorage(key(b"totalExecutedPerDay", &<[u8; 32]>::from(day)))
+    }
+
+    #[constant]
+    fn getCurrentDay(&self) -> U256 {
+        U256::from(timestamp() / DAY)
+    }
+
+    /// Returns `true` if a user request for `amount` may be made today.
+    #[constant]
+    fn withinLimit(&self, amount: U256) -> bool {
+        let (next_limit, overflow) = self
+            .totalSpentPerDay(self.getCurrentDay())
+            .overflowing_add(amount);
+        !overflow
+            && self.dailyLimit() >= next_limit
+            && amount <= self.maxPerTx()
+            && amount >= self.minPerTx()
+    }
+
+    /// Returns `true` if an affirmation for `amount` may be executed today.
+    #[constant]
+    fn withinExecutionLimit(&self, amount: U256) -> bool {
+        let (next_limit, overflow) = self
+            .totalExecutedPerDay(self.getCurrentDay())
+            .overflowing_add(amount);
+        !overflow && self.executionDailyLimit() >= next_limit && amount <= self.executionMaxPerTx()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not a limits manager, or if `dailyLimit` is
+    /// neither zero nor greater than `maxPerTx`.
+    fn setDailyLimit(&mut self, dailyLimit: U256) {
+        self.onlyRole(LIMITS_MANAGER_ROLE);
+        require(
+            dailyLimit.is_zero() || dailyLimit > self.maxPerTx(),
+            BridgeError::InvalidLimit,
+        );
+        self.setUintStorage(key(b"dailyLimit", &[]), dailyLimit);
+        self.DailyLimitChanged(dailyLimit);
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not a limits manager, or if `maxPerTx` is not
+    /// between `minPerTx` and `dailyLimit` (exclusive).
+    fn setMaxPerTx(&mut self, maxPerTx: U256) {
+        self.onlyRole(LIMITS_MANAGER_ROLE);
+        check_max_per_tx(maxPerTx, self.minPerTx(), self.dailyLimit());
+        self.setUintStorage(key(b"maxPerTx", &[]), maxPerTx);
+        self.MaxPerTxChanged(maxPerTx);
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not a limits manager, or if `minPerTx` is not
+    /// between zero and `maxPerTx` (exclusive).
+    fn setMinPerTx(&mut self, minPerTx: U256) {
+        self.onlyRole(LIMITS_MANAGER_ROLE);
+        require(
+            !minPerTx.is_zero() && minPerTx < self.dailyLimit() && minPerTx < self.maxPerTx(),
+            BridgeError::InvalidLimit,
+        );
+        self.setUintStorage(key(b"minPerTx", &[]), minPerTx);
+        self.MinPerTxChanged(minPerTx);
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not a limits manager, or if `dailyLimit` is
+    /// neither zero nor greater than `executionMaxPerTx`.
+    fn setExecutionDailyLimit(&mut self, dailyLimit: U256) {
+        self.onlyRole(LIMITS_MANAGER_ROLE);
+        require(
+            dailyLimit.is_zero() || dailyLimit > self.executionMaxPerTx(),
+            BridgeError::InvalidLimit,
+        );
+        self.setUintStorage(key(b"executionDailyLimit", &[]), dailyLimit);
+        self.ExecutionDailyLimitChanged(dailyLimit);
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not a limits manager, or if `maxPerTx` is not
+    /// between zero and `executionDailyLimit` (exclusive).
+    fn setExecutionMaxPerTx(&mut self, maxPerTx: U256) {
+        self.onlyRole(LIMITS_MANAGER_ROLE);
+        check_max_per_tx(maxPerTx, U256::zero(), self.executionDailyLimit());
+        self.setUintStorage(key(b"executionMaxPerTx", &[]), maxPerTx);
+        self.ExecutionMaxPerTxChanged(maxPerTx);
+    }
+}
+
+/// Checks that `max_per_tx` lies strictly between `min_per_tx` and
+/// `daily_limit`.
+///
+/// # Panics
+///
+/// Panics if it does not.
+fn check_max_per_tx(max_per_tx: U256, min_per_tx: U256, daily_limit: U256) {
+    require(
+        max_per_tx > min_per_tx && max_per_tx < daily_limit,
+        BridgeError::InvalidLimit,
+    );
+}
+
+/// Bookkeeping for [`BridgeLimitsTrait`] that must not be part of the ABI.
+pub trait BridgeLimitsInternal: BridgeLimitsTrait {
+    /// Records a user request for `amount`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request is not within the limits.
+    fn spend(&mut self, amount: U256) {
+        require(self.withinLimit(amount), BridgeError::ExceedsLimits);
+        let day = self.getCurrentDay();
+        let spent = self.totalSpentPerDay(day);
+        self.setUintStorage(
+            key(b"totalSpentPerDay", &<[u8; 32]>::from(day)),
+            spent + amount,
+        );
+    }
+
+    /// Records the execution of an affirmation for `amount`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the affirmation is not within the execution limits.
+    fn execute(&mut self, amount: U256) {
+        require(
+            self.withinExecutionLimit(amount),
+            BridgeError::ExceedsExecutionLimits,
+        );
+        let day = self.getCurrentDay();
+        let executed = self.totalExecutedPerDay(day);
+        self.setUintStorage(
+            key(b"totalExecutedPerDay", &<[u8; 32]>::from(day)),
+            executed + amount,
+        );
+    }
+}
+
+impl<T: BridgeLimitsTrait + ?Sized> BridgeLimitsInternal for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::native;
+    use crate::upgradeability::eternal_storage::EternalStorage;
+    use pwasm_std::types::Address;
+
+    struct Bridge;
+
+    impl EternalStorage for Bridge {}
+
+    impl AccessControlTrait for Bridge {}
+
+    impl BridgeLimitsTrait for Bridge {}
+
+    fn manager() -> Address {
+        Address::from([0xaa; 20])
+    }
+
+    /// A bridge whose limits are all unset, with `manager` as its limits
+    /// manager and the sender.
+    fn bridge() -> Bridge {
+        native::reset();
+        native::with(|ext| ext.sender = manager());
+        let mut bridge = Bridge;
+        bridge.setup_roles(manager(), &[LIMITS_MANAGER_ROLE]);
+        bridge
+    }
+
+    fn reverts_with<R>(f: impl FnOnce() -> R) -> Option<BridgeError> {
+        BridgeError::decode(&native::catch_revert(f).err()?)
+    }
+
+    #[test]
+    fn bounds_max_per_tx() {
+        let mut bridge = bridge();
+        bridge.setDailyLimit(1000.into());
+        bridge.setMaxPerTx(100.into());
+        bridge.setMinPerTx(10.into());
+        for &max in &[0u64, 10, 1000, 1001] {
+            assert_eq!(
+                reverts_with(|| bridge.setMaxPerTx(max.into())),
+                Some(BridgeError::InvalidLimit),
+                "{}",
+                max
+            );
+        }
+        bridge.setMaxPerTx(11.into());
+        bridge.setMaxPerTx(999.into());
+        assert_eq!(bridge.maxPerTx(), 999.into());
+    }
+
+    #[test]
+    fn bounds_execution_max_per_tx() {
+        let mut bridge = bridge();
+        bridge.setExecutionDailyLimit(1000.into());
+        for &max in &[0u64, 1000, 1001] {
+            assert_eq!(
+                reverts_with(|| bridge.setExecutionMaxPerTx(max.into())),
+                Some(BridgeError::InvalidLimit),
+                "{}",
+                max
+            );
+        }
+        bridge.setExecutionMaxPerTx(1.into());
+        bridge.setExecutionMaxPerTx(999.into());
+        assert_eq!(bridge.executionMaxPerTx(), 999.into());
+    }
+
+    #[test]
+    fn daily_limit_of_zero_stops_transfers() {
+        let mut bridge = bridge();
+        bridge.setDailyLimit(1000.into());
+        bridge.setMaxPerTx(100.into());
+        bridge.setMinPerTx(10.into());
+        bridge.setExecutionDailyLimit(1000.into());
+        bridge.setExecutionMaxPerTx(100.into());
+        assert!(bridge.withinLimit(50.into()));
+        assert!(bridge.withinExecutionLimit(50.into()));
+        bridge.setDailyLimit(U256::zero());
+        bridge.setExecutionDailyLimit(U256::zero());
+        assert!(!bridge.withinLimit(50.into()));
+        assert!(!bridge.withinExecutionLimit(50.into()));
+        assert_eq!(
+            reverts_with(|| bridge.setDailyLimit(100.into())),
+            Some(BridgeError::InvalidLimit)
+        );
+        bridge.setDailyLimit(101.into());
+        assert!(bridge.withinLimit(50.into()));
+    }
+
+    #[test]
+    fn only_limits_manager_sets_limits() {
+        let mut bridge = bridge();
+        native::with(|ext| ext.sender = Address::from([0xbb; 20]));
+        assert_eq!(
+            reverts_with(|| bridge.setDailyLimit(1000.into())),
+            Some(BridgeError::MissingRole)
+        );
+        assert_eq!(
+            reverts_with(|| bridge.setExecutionDailyLimit(1000.into())),
+            Some(BridgeError::MissingRole)
+        );
+    }
+}
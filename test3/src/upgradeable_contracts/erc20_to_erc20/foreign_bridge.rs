@@ -0,0 +1,138 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+use super::super::access_control::AccessControlInternal;
+use super::super::basic_foreign_bridge::{
+    BasicForeignBridgeContract, ForeignBridgeMode, FOREIGN_BRIDGE_OWNER_ROLES,
+};
+use super::super::initializable::Initializable;
+use super::super::ownable::OwnableInternal;
+use crate::bridge_validators::ValidatorContract;
+use crate::erc677_bridge_token::ERC20Client;
+use crate::error::{require, BridgeError};
+use crate::ext::gas_left;
+use crate::upgradeability::eternal_storage::{key, EternalStorage};
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::types::{Address, U256};
+
+/// Users deposit tokens by transferring them to the bridge with a plain ERC20
+/// `transfer`.  The validators watch for `Transfer` events to the bridge, so
+/// this contract has no deposit method.
+#[eth_abi(ForeignBridgeErcToErc)]
+pub trait ForeignBridgeErcToErcInterface {
+    /// Sets the validator contract, the token held in custody, and the owner
+    /// of the bridge.
+    /// The owner is also granted the admin and pauser roles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is neither an admin nor an upgrader, if the
+    /// bridge has already been initialized, or if any of the addresses is
+    /// zero.
+    fn initialize(
+        &mut self,
+        validatorContract: Address,
+        erc20token: Address,
+        owner: Address,
+    ) -> bool;
+
+    #[constant]
+    fn isInitialized(&self) -> bool;
+
+    #[constant]
+    fn deployedAtBlock(&self) -> U256;
+
+    #[constant]
+    fn erc20token(&self) -> Address;
+}
+
+/// Releases tokens held in custody.
+pub struct ErcToErcForeignMode;
+
+impl EternalStorage for ErcToErcForeignMode {}
+
+impl ErcToErcForeignMode {
+    fn erc20token(&self) -> Address {
+        self.addressStorage(key(b"erc20token", &[]))
+    }
+}
+
+impl ForeignBridgeMode for ErcToErcForeignMode {
+    fn onExecuteMessage(&mut self, recipient: Address, amount: U256) -> bool {
+        ERC20Client::new(self.erc20token())
+            .gas(gas_left())
+            .transfer(recipient, amount)
+    }
+}
+
+pub type ForeignBridgeErcToErcContract = BasicForeignBridgeContract<ErcToErcForeignMode>;
+
+impl ForeignBridgeErcToErcInterface for ForeignBridgeErcToErcContract {
+    fn initialize(
+        &mut self,
+        validatorContract: Address,
+        erc20token: Address,
+        owner: Address,
+    ) -> bool {
+        require(erc20token != Address::zero(), BridgeError::ZeroAddress);
+        require(owner != Address::zero(), BridgeError::ZeroAddress);
+        self.onlyInitializer();
+        self.set_initialized();
+        ValidatorContract.set_address(validatorContract);
+        self.setAddressStorage(key(b"erc20token", &[]), erc20token);
+        self.setOwner(owner);
+        self.setup_roles(owner, FOREIGN_BRIDGE_OWNER_ROLES);
+        self.is_initialized()
+    }
+
+    fn isInitialized(&self) -> bool {
+        self.is_initialized()
+    }
+
+    fn deployedAtBlock(&self) -> U256 {
+        self.deployed_at_block()
+    }
+
+    fn erc20token(&self) -> Address {
+        ErcToErcForeignMode.erc20token()
+    }
+}
+
+/// Runs the bridge on the call data `input`, returning its output.
+#[cfg(any(feature = "foreign-bridge-erc-to-erc", not(target_arch = "wasm32")))]
+pub fn run(input: &[u8]) -> pwasm_std::Vec<u8> {
+    use super::super::basic_foreign_bridge::dispatch;
+    use pwasm_abi::eth::EndpointInterface;
+    let contract = ForeignBridgeErcToErcContract::new(ErcToErcForeignMode);
+    match dispatch(contract, input) {
+        Ok(result) => result,
+        Err(contract) => ForeignBridgeErcToErc::new(contract).dispatch(input),
+    }
+}
+
+#[cfg(feature = "foreign-bridge-erc-to-erc")]
+#[no_mangle]
+pub fn call() {
+    crate::ext::ret(&run(&crate::ext::input()));
+}
+
+#[cfg(feature = "foreign-bridge-erc-to-erc")]
+#[no_mangle]
+pub fn deploy() {
+    // The bridge runs behind a proxy, so it is set up by `initialize`
+    // rather than by a constructor.
+}
@@ -0,0 +1,164 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+use super::super::access_control::AccessControlInternal;
+use super::super::basic_home_bridge::{
+    BasicHomeBridgeContract, HomeBridgeMode, HOME_BRIDGE_OWNER_ROLES,
+};
+use super::super::initializable::Initializable;
+use super::super::ownable::{claim_ownership, OwnableInternal};
+use crate::bridge_validators::ValidatorContract;
+use crate::erc677_bridge_token::{BurnableMintableERC677TokenClient, IERC677Receiver};
+use crate::error::{require, BridgeError};
+use crate::ext::{gas_left, sender};
+use crate::upgradeability::eternal_storage::{key, EternalStorage};
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::{
+    types::{Address, U256},
+    Vec,
+};
+
+#[eth_abi(HomeBridgeErcToErc)]
+pub trait HomeBridgeErcToErcInterface {
+    /// Sets the validator contract, the bridged token, and the owner of the
+    /// bridge.  The bridge must be the owner of `erc677token`, or its
+    /// pending owner, in which case it claims ownership of it.
+    /// The owner is also granted the admin, limits manager, and pauser
+    /// roles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is neither an admin nor an upgrader, if the
+    /// bridge has already been initialized, or if any of the addresses is
+    /// zero.
+    fn initialize(
+        &mut self,
+        validatorContract: Address,
+        erc677token: Address,
+        owner: Address,
+    ) -> bool;
+
+    #[constant]
+    fn isInitialized(&self) -> bool;
+
+    #[constant]
+    fn deployedAtBlock(&self) -> U256;
+
+    #[constant]
+    fn erc677token(&self) -> Address;
+}
+
+/// Mints bridged tokens.
+pub struct ErcToErcHomeMode;
+
+impl EternalStorage for ErcToErcHomeMode {}
+
+impl ErcToErcHomeMode {
+    fn erc677token(&self) -> Address {
+        self.addressStorage(key(b"erc677token", &[]))
+    }
+}
+
+impl HomeBridgeMode for ErcToErcHomeMode {
+    fn onExecuteAffirmation(&mut self, recipient: Address, value: U256) -> bool {
+        BurnableMintableERC677TokenClient::new(self.erc677token())
+            .gas(gas_left())
+            .mint(recipient, value)
+    }
+}
+
+pub type HomeBridgeErcToErcContract = BasicHomeBridgeContract<ErcToErcHomeMode>;
+
+impl HomeBridgeErcToErcInterface for HomeBridgeErcToErcContract {
+    fn initialize(
+        &mut self,
+        validatorContract: Address,
+        erc677token: Address,
+        owner: Address,
+    ) -> bool {
+        require(erc677token != Address::zero(), BridgeError::ZeroAddress);
+        require(owner != Address::zero(), BridgeError::ZeroAddress);
+        self.onlyInitializer();
+        self.set_initialized();
+        ValidatorContract.set_address(validatorContract);
+        self.setAddressStorage(key(b"erc677token", &[]), erc677token);
+        self.setOwner(owner);
+        self.setup_roles(owner, HOME_BRIDGE_OWNER_ROLES);
+        claim_ownership(erc677token);
+        self.is_initialized()
+    }
+
+    fn isInitialized(&self) -> bool {
+        self.is_initialized()
+    }
+
+    fn deployedAtBlock(&self) -> U256 {
+        self.deployed_at_block()
+    }
+
+    fn erc677token(&self) -> Address {
+        ErcToErcHomeMode.erc677token()
+    }
+}
+
+impl IERC677Receiver for HomeBridgeErcToErcContract {
+    /// Called by the token after `transferAndCall`.  Burns the tokens
+    /// received and requests that the original tokens be released to `from`
+    /// on the foreign chain.
+    fn onTokenTransfer(&mut self, from: Address, value: U256, _data: Vec<u8>) -> bool {
+        let token = self.erc677token();
+        require(sender() == token, BridgeError::NotBridgedToken);
+        BurnableMintableERC677TokenClient::new(token)
+            .gas(gas_left())
+            .burn(value);
+        self.requestSignature(from, value);
+        true
+    }
+}
+
+/// Runs the bridge on the call data `input`, returning its output.
+#[cfg(any(feature = "home-bridge-erc-to-erc", not(target_arch = "wasm32")))]
+pub fn run(input: &[u8]) -> Vec<u8> {
+    use super::super::basic_home_bridge::dispatch;
+    use crate::dispatch::calls_any;
+    use crate::erc677_bridge_token::{ERC677Receiver, ERC677_RECEIVER_METHODS};
+    use pwasm_abi::eth::EndpointInterface;
+    let contract = HomeBridgeErcToErcContract::new(ErcToErcHomeMode);
+    match dispatch(contract, input) {
+        Ok(result) => result,
+        Err(contract) => {
+            if calls_any(input, ERC677_RECEIVER_METHODS) {
+                ERC677Receiver::new(contract).dispatch(input)
+            } else {
+                HomeBridgeErcToErc::new(contract).dispatch(input)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "home-bridge-erc-to-erc")]
+#[no_mangle]
+pub fn call() {
+    crate::ext::ret(&run(&crate::ext::input()));
+}
+
+#[cfg(feature = "home-bridge-erc-to-erc")]
+#[no_mangle]
+pub fn deploy() {
+    // The bridge runs behind a proxy, so it is set up by `initialize`
+    // rather than by a constructor.
+}
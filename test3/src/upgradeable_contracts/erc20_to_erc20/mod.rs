@@ -0,0 +1,26 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Bridges an existing ERC20 token on the foreign chain to an ERC677 token on
+//! the home chain.  Tokens sent to the foreign bridge are held in custody, and
+//! the home bridge mints the same amount of bridged tokens.  Bridged tokens
+//! sent back to the home bridge are burnt, and the foreign bridge releases
+//! the original tokens.
+//!
+//! Both sides use the same events and message format as the other bridge
+//! modes, so one oracle can serve all of them.
+pub mod foreign_bridge;
+pub mod home_bridge;
@@ -0,0 +1,204 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+use super::super::access_control::{AccessControlInternal, DEFAULT_ADMIN_ROLE};
+use super::super::basic_home_bridge::{
+    BasicHomeBridgeContract, HomeBridgeMode, HOME_BRIDGE_OWNER_ROLES,
+};
+use super::super::initializable::Initializable;
+use super::super::ownable::OwnableInternal;
+use crate::block_reward::{BlockRewardContract, IBlockReward};
+use crate::bridge_validators::ValidatorContract;
+use crate::error::{require, revert, BridgeError};
+use crate::ext::{address, call, gas_left, sender, value};
+use crate::upgradeability::eternal_storage::{key, EternalStorage};
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::types::{Address, U256};
+
+#[eth_abi(HomeBridgeErcToNative)]
+pub trait HomeBridgeErcToNativeInterface {
+    /// Sets the validator contract, the block reward contract, and the owner
+    /// of the bridge.
+    /// The owner is also granted the admin, limits manager, and pauser
+    /// roles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is neither an admin nor an upgrader, if the
+    /// bridge has already been initialized, if any of the addresses is zero,
+    /// or if `blockReward` is not a block reward contract.
+    fn initialize(
+        &mut self,
+        validatorContract: Address,
+        blockReward: Address,
+        owner: Address,
+    ) -> bool;
+
+    /// Changes the block reward contract.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an admin, or if `blockReward` is not a
+    /// block reward contract.
+    fn setBlockRewardContract(&mut self, blockReward: Address);
+
+    #[constant]
+    fn blockRewardContract(&self) -> Address;
+
+    /// The total amount of native coins burnt by the bridge.  This can never
+    /// exceed the amount the block reward contract has minted for the bridge.
+    #[constant]
+    fn totalBurntCoins(&self) -> U256;
+
+    #[constant]
+    fn isInitialized(&self) -> bool;
+
+    #[constant]
+    fn deployedAtBlock(&self) -> U256;
+}
+
+/// Has the block reward contract mint native coins.
+pub struct ErcToNativeHomeMode;
+
+impl EternalStorage for ErcToNativeHomeMode {}
+
+impl ErcToNativeHomeMode {
+    fn block_reward(&self) -> BlockRewardContract {
+        let block_reward = self.addressStorage(key(b"blockRewardContract", &[]));
+        require(
+            block_reward != Address::zero(),
+            BridgeError::BlockRewardNotSet,
+        );
+        BlockRewardContract(block_reward)
+    }
+}
+
+impl HomeBridgeMode for ErcToNativeHomeMode {
+    fn onExecuteAffirmation(&mut self, recipient: Address, value: U256) -> bool {
+        self.block_reward().add_extra_receiver(value, recipient);
+        true
+    }
+}
+
+pub type HomeBridgeErcToNativeContract = BasicHomeBridgeContract<ErcToNativeHomeMode>;
+
+impl HomeBridgeErcToNativeContract {
+    /// Sets the block reward contract.  A contract that allows no bridges
+    /// cannot be a working block reward contract, so it is rejected.
+    fn set_block_reward(&mut self, block_reward: Address) {
+        require(block_reward != Address::zero(), BridgeError::ZeroAddress);
+        require(
+            !BlockRewardContract(block_reward)
+                .bridges_allowed_length()
+                .is_zero(),
+            BridgeError::NotBlockReward,
+        );
+        self.setAddressStorage(key(b"blockRewardContract", &[]), block_reward)
+    }
+
+    /// The payable fallback.  Burns the coins sent, and requests that the
+    /// same amount of tokens be released to the sender on the foreign chain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bridge would burn more coins than the block reward
+    /// contract has minted for it.
+    pub fn fallback(&mut self) {
+        let value = value();
+        let minted = ErcToNativeHomeMode
+            .block_reward()
+            .minted_totally_by_bridge(address());
+        let burnt = self.totalBurntCoins();
+        require(
+            burnt <= minted && value <= minted - burnt,
+            BridgeError::BurnExceedsMinted,
+        );
+        self.setUintStorage(key(b"totalBurntCoins", &[]), burnt + value);
+        call(gas_left(), &Address::zero(), value, &[], &mut [])
+            .unwrap_or_else(|_| revert(BridgeError::TransferFailed));
+        self.requestSignature(sender(), value)
+    }
+}
+
+impl HomeBridgeErcToNativeInterface for HomeBridgeErcToNativeContract {
+    fn initialize(
+        &mut self,
+        validatorContract: Address,
+        blockReward: Address,
+        owner: Address,
+    ) -> bool {
+        require(owner != Address::zero(), BridgeError::ZeroAddress);
+        self.onlyInitializer();
+        self.set_initialized();
+        ValidatorContract.set_address(validatorContract);
+        self.set_block_reward(blockReward);
+        self.setOwner(owner);
+        self.setup_roles(owner, HOME_BRIDGE_OWNER_ROLES);
+        self.is_initialized()
+    }
+
+    fn setBlockRewardContract(&mut self, blockReward: Address) {
+        self.onlyRole(DEFAULT_ADMIN_ROLE);
+        self.set_block_reward(blockReward)
+    }
+
+    fn blockRewardContract(&self) -> Address {
+        self.addressStorage(key(b"blockRewardContract", &[]))
+    }
+
+    fn totalBurntCoins(&self) -> U256 {
+        self.uintStorage(key(b"totalBurntCoins", &[]))
+    }
+
+    fn isInitialized(&self) -> bool {
+        self.is_initialized()
+    }
+
+    fn deployedAtBlock(&self) -> U256 {
+        self.deployed_at_block()
+    }
+}
+
+/// Runs the bridge on the call data `input`, returning its output.
+#[cfg(any(feature = "home-bridge-erc-to-native", not(target_arch = "wasm32")))]
+pub fn run(input: &[u8]) -> pwasm_std::Vec<u8> {
+    use super::super::basic_home_bridge::dispatch;
+    use pwasm_abi::eth::EndpointInterface;
+    let mut contract = HomeBridgeErcToNativeContract::new(ErcToNativeHomeMode);
+    if input.is_empty() {
+        contract.fallback();
+        pwasm_std::Vec::new()
+    } else {
+        match dispatch(contract, input) {
+            Ok(result) => result,
+            Err(contract) => HomeBridgeErcToNative::new(contract).dispatch(input),
+        }
+    }
+}
+
+#[cfg(feature = "home-bridge-erc-to-native")]
+#[no_mangle]
+pub fn call() {
+    crate::ext::ret(&run(&crate::ext::input()));
+}
+
+#[cfg(feature = "home-bridge-erc-to-native")]
+#[no_mangle]
+pub fn deploy() {
+    // The bridge runs behind a proxy, so it is set up by `initialize`
+    // rather than by a constructor.
+}
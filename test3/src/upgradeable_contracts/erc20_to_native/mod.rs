@@ -0,0 +1,22 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Bridges an ERC20 token on the foreign chain to the native coin of the home
+//! chain.  The home bridge has the block reward contract mint native coins,
+//! and burns the coins sent back to it.  The foreign side holds the ERC20
+//! token in custody, exactly as in the ERC20-to-ERC20 mode, so it is
+//! `erc20_to_erc20::foreign_bridge`.
+pub mod home_bridge;
@@ -0,0 +1,227 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+use super::access_control::{AccessControlInternal, AccessControlTrait, DEFAULT_ADMIN_ROLE};
+use crate::bridge_validators::IBridgeValidators;
+use crate::error::{require, BridgeError};
+use crate::upgradeability::eternal_storage::key;
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::{
+    types::{Address, U256},
+    Vec,
+};
+
+/// Methods of the `FeeManager` endpoint.
+pub const FEE_MANAGER_METHODS: &[&str] = &[
+    "getHomeFee()",
+    "getForeignFee()",
+    "setHomeFee(uint256)",
+    "setForeignFee(uint256)",
+];
+
+/// Fees are fractions of the value transferred, scaled by 10^18.
+fn max_fee() -> U256 {
+    U256::from(1_000_000_000_000_000_000u64)
+}
+
+/// Which direction a fee is charged on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FeeType {
+    /// Transfers from the home chain to the foreign chain.  The fee is
+    /// deducted when the user makes the request, and distributed once the
+    /// validators have collected enough signatures.
+    Home,
+    /// Transfers from the foreign chain to the home chain.  The fee is
+    /// deducted and distributed when the affirmation is executed.
+    Foreign,
+}
+
+/// # FeeManager
+///
+/// Charges a fee on bridge transfers and divides it among the current
+/// validators, paying each validator's share to its reward address.  Both
+/// fees start at zero, so a bridge moves value fee-free until an admin sets
+/// them.
+#[eth_abi(FeeManager)]
+pub trait FeeManagerTrait: AccessControlTrait {
+    #[event]
+    fn HomeFeeUpdated(&mut self, fee: U256);
+
+    #[event]
+    fn ForeignFeeUpdated(&mut self, fee: U256);
+
+    #[event]
+    fn FeeDistributed(&mut self, feeAmount: U256, indexed_transactionHash: [u8; 32]);
+
+    #[constant]
+    fn getHomeFee(&self) -> U256 {
+        self.uintStorage(key(b"homeFee", &[]))
+    }
+
+    #[constant]
+    fn getForeignFee(&self) -> U256 {
+        self.uintStorage(key(b"foreignFee", &[]))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an admin, or if `fee` is 10^18 (100%)
+    /// or more.
+    fn setHomeFee(&mut self, fee: U256) {
+        self.onlyRole(DEFAULT_ADMIN_ROLE);
+        require(fee < max_fee(), BridgeError::InvalidFee);
+        self.setUintStorage(key(b"homeFee", &[]), fee);
+        self.HomeFeeUpdated(fee);
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not an admin, or if `fee` is 10^18 (100%)
+    /// or more.
+    fn setForeignFee(&mut self, fee: U256) {
+        self.onlyRole(DEFAULT_ADMIN_ROLE);
+        require(fee < max_fee(), BridgeError::InvalidFee);
+        self.setUintStorage(key(b"foreignFee", &[]), fee);
+        self.ForeignFeeUpdated(fee);
+    }
+}
+
+/// Fee calculation for [`FeeManagerTrait`] that must not be part of the ABI.
+pub trait FeeManagerInternal: FeeManagerTrait {
+    /// Computes the fee of type `fee_type` charged on `value`.
+    fn calculate_fee(&self, value: U256, fee_type: FeeType) -> U256 {
+        let fee = match fee_type {
+            FeeType::Home => self.getHomeFee(),
+            FeeType::Foreign => self.getForeignFee(),
+        };
+        let (product, overflow) = value.overflowing_mul(fee);
+        require(!overflow, BridgeError::Overflow);
+        product / max_fee()
+    }
+
+    /// Divides `fee` evenly among the current validators.  Returns the reward
+    /// address of each validator and the amount it is owed.  Any remainder
+    /// goes to the first validator.
+    fn distribute_fee(
+        &self,
+        fee: U256,
+        validators: &dyn IBridgeValidators,
+    ) -> Vec<(Address, U256)> {
+        let validator_list = validators.validator_list();
+        if fee.is_zero() || validator_list.is_empty() {
+            return Vec::new();
+        }
+        let count = U256::from(validator_list.len());
+        let share = fee / count;
+        let remainder = fee - share * count;
+        validator_list
+            .into_iter()
+            .enumerate()
+            .map(|(i, validator)| {
+                let reward_address = match validators.reward_address(validator) {
+                    reward_address if reward_address.is_zero() => validator,
+                    reward_address => reward_address,
+                };
+                let amount = if i == 0 { share + remainder } else { share };
+                (reward_address, amount)
+            })
+            .collect()
+    }
+}
+
+impl<T: FeeManagerTrait + ?Sized> FeeManagerInternal for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::native;
+    use crate::upgradeability::eternal_storage::EternalStorage;
+    use std::vec;
+
+    struct Bridge;
+
+    impl EternalStorage for Bridge {}
+
+    impl AccessControlTrait for Bridge {}
+
+    impl FeeManagerTrait for Bridge {}
+
+    /// Validators `1..=count`, each of which is paid at its address plus
+    /// 100, except for validator 1, which has no reward address.
+    struct Validators(u8);
+
+    impl IBridgeValidators for Validators {
+        fn is_validator(&self, _validator: Address) -> bool {
+            true
+        }
+
+        fn required_signatures(&self) -> usize {
+            1
+        }
+
+        fn owner(&self) -> Address {
+            Address::zero()
+        }
+
+        fn validator_list(&self) -> Vec<Address> {
+            (1..=self.0).map(|i| Address::from([i; 20])).collect()
+        }
+
+        fn reward_address(&self, validator: Address) -> Address {
+            match validator[0] {
+                1 => Address::zero(),
+                i => Address::from([i + 100; 20]),
+            }
+        }
+    }
+
+    /// One tenth of the value, i.e. 10%.
+    fn ten_percent() -> U256 {
+        max_fee() / 10
+    }
+
+    #[test]
+    fn calculates_fee() {
+        native::reset();
+        Bridge.setUintStorage(key(b"homeFee", &[]), ten_percent());
+        assert_eq!(Bridge.calculate_fee(1000.into(), FeeType::Home), 100.into());
+        assert_eq!(Bridge.calculate_fee(999.into(), FeeType::Home), 99.into());
+        assert!(Bridge
+            .calculate_fee(1000.into(), FeeType::Foreign)
+            .is_zero());
+    }
+
+    #[test]
+    fn divides_fee_among_validators() {
+        native::reset();
+        assert_eq!(
+            Bridge.distribute_fee(10.into(), &Validators(3)),
+            vec![
+                (Address::from([1; 20]), 4.into()),
+                (Address::from([102; 20]), 3.into()),
+                (Address::from([103; 20]), 3.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn pays_nothing_without_validators() {
+        native::reset();
+        assert!(Bridge.distribute_fee(10.into(), &Validators(0)).is_empty());
+        assert!(Bridge.distribute_fee(0.into(), &Validators(3)).is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+use super::super::upgradeability::eternal_storage::{key, EternalStorage};
+use crate::error::{require, BridgeError};
+use crate::ext::block_number;
+use pwasm_std::types::U256;
+
+/// Tracks whether a contract behind a proxy has been initialized.  Upgradeable
+/// contracts cannot use constructors, because the constructor would run
+/// against the implementation's storage rather than the proxy's, so they are
+/// set up by an `initialize` method that may only be called once.
+pub trait Initializable: EternalStorage {
+    fn is_initialized(&self) -> bool {
+        self.boolStorage(key(b"isInitialized", &[]))
+    }
+
+    /// The block at which the contract was initialized.
+    fn deployed_at_block(&self) -> U256 {
+        self.uintStorage(key(b"deployedAtBlock", &[]))
+    }
+
+    /// Marks the contract as initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the contract has already been initialized.
+    fn set_initialized(&mut self) {
+        require(!self.is_initialized(), BridgeError::AlreadyInitialized);
+        self.setUintStorage(key(b"deployedAtBlock", &[]), block_number().into());
+        self.setBoolStorage(key(b"isInitialized", &[]), true);
+    }
+}
+
+impl<T: EternalStorage + ?Sized> Initializable for T {}
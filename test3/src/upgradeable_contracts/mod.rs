@@ -15,25 +15,37 @@
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <https://www.gnu.org/licenses/>.
 mod Validateable;
+pub mod access_control;
+mod basic_foreign_bridge;
 mod basic_home_bridge;
-mod ownable;
+mod bridge_limits;
+pub mod erc20_to_erc20;
+pub mod erc20_to_native;
+mod fee_manager;
+mod initializable;
+pub mod native_to_erc20;
+pub mod ownable;
+mod pausable;
+use self::access_control::{
+    AccessControlInternal, AccessControlTrait, DEFAULT_ADMIN_ROLE, VALIDATOR_MANAGER_ROLE,
+};
 use super::bridge_validators::IBridgeValidators;
+use super::upgradeability::eternal_storage::EternalStorage;
+use crate::error::{require, BridgeError};
+use crate::ext::{block_number, sender};
 use core::usize;
 use pwasm_abi::eth::EndpointInterface;
 use pwasm_abi_derive::eth_abi;
-use pwasm_ethereum::{block_number, read, sender, write};
 use pwasm_std::{
-    types::{Address, H256, U256},
+    types::{Address, U256},
     Vec,
 };
-extern crate tiny_keccak;
-use tiny_keccak::Keccak;
 
-include!(concat!(env!("OUT_DIR"), "/hashes.rs"));
+include!(concat!(env!("OUT_DIR"), "/bridge_validators_storage.rs"));
 
 #[allow(non_snake_case)]
-#[eth_abi(BridgeValidators)]
-trait BridgeValidatorsTrait: Ownable {
+#[eth_abi(BridgeValidators, BridgeValidatorsClient)]
+pub trait BridgeValidatorsTrait {
     /// Fired when a validator is added.
     #[event]
     fn ValidatorAdded(&mut self, indexed_validator: Address);
@@ -46,6 +58,10 @@ trait BridgeValidatorsTrait: Ownable {
     #[event]
     fn RequiredSignaturesChanged(&mut self, requiredSignatures: U256);
 
+    /// The constructor grants the sender account the admin role, so that it
+    /// may initialize the contract when it is not deployed behind a proxy.
+    fn constructor(&mut self);
+
     fn initialize(
         &mut self,
         requiredSignatures: U256,
@@ -53,8 +69,28 @@ trait BridgeValidatorsTrait: Ownable {
         owner: Address,
     ) -> bool;
 
+    /// Fired when the address that fees are paid to for a validator changes.
+    #[event]
+    fn ValidatorRewardAddressChanged(&mut self, indexed_validator: Address, reward: Address);
+
     fn addValidator(&mut self, validator: Address);
 
+    /// Adds `validator`, paying the fees it earns to `reward`.
+    fn addRewardableValidator(&mut self, validator: Address, reward: Address);
+
+    /// Changes the address that fees earned by `validator` are paid to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is neither a validator manager nor
+    /// `validator`, if `validator` is not a validator, or if `reward` is the
+    /// zero address.
+    fn setValidatorRewardAddress(&mut self, validator: Address, reward: Address);
+
+    /// Returns the address that fees earned by `validator` are paid to.
+    #[constant]
+    fn getValidatorRewardAddress(&self, validator: Address) -> Address;
+
     fn removeValidator(&mut self, validator: Address);
 
     fn setRequiredSignatures(&mut self, requiredSignatures: U256);
@@ -71,6 +107,28 @@ trait BridgeValidatorsTrait: Ownable {
     #[constant]
     fn validators(&self, validator: Address) -> bool;
 
+    /// Returns all validators, in the order they were added.  Validators
+    /// added by an implementation that predates the list are left out until
+    /// they are linked with `linkValidator`.
+    #[constant]
+    fn validatorList(&self) -> Vec<Address>;
+
+    /// Appends `validator`, added by an implementation that predates the
+    /// validator list, to `validatorList()`.  Once every such validator is
+    /// linked, the list has `validatorCount()` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not a validator manager, if `validator` is
+    /// not a validator, or if it is already in the list.
+    fn linkValidator(&mut self, validator: Address);
+
+    /// Returns the validator after `validator` in `validatorList()`, or the
+    /// zero address if `validator` is the last one.  If `validator` is the
+    /// zero address, returns the first validator.
+    #[constant]
+    fn getNextValidator(&self, validator: Address) -> Address;
+
     #[constant]
     fn isValidator(&self, validator: Address) -> bool;
 
@@ -106,88 +164,179 @@ trait BridgeValidatorsTrait: Ownable {
     #[constant]
     fn owner(&self) -> Address;
 
+    /// Event to show that a transfer of ownership has been started, and
+    /// that `newOwner` may now claim ownership.
+    #[allow(non_snake_case)]
+    #[event]
+    fn OwnershipTransferStarted(&mut self, previousOwner: Address, newOwner: Address);
+
+    /// Event to show that a pending transfer of ownership was cancelled.
+    #[allow(non_snake_case)]
+    #[event]
+    fn OwnershipTransferCancelled(&mut self, pendingOwner: Address);
+
+    /// Tells the address that may claim ownership, or the zero address if no
+    /// transfer of ownership is pending.
+    #[allow(non_snake_case)]
+    #[constant]
+    fn pendingOwner(&self) -> Address {
+        get_pendingOwner()
+    }
+
     /**
-     * Allows the current owner to transfer control of the contract to a newOwner.
+     * Allows the current owner to start transferring control of the contract to a newOwner.
+     * Ownership only changes once `newOwner` calls `claimOwnership`, so a
+     * mistyped address cannot take over the contract.
      *
      * `newOwner`: the address to transfer ownership to.
      */
     #[allow(non_snake_case)]
     fn transferOwnership(&mut self, newOwner: Address) {
         self.onlyOwner();
-        assert_ne!(newOwner, Address::zero());
-        self.OwnershipTransferred(self.owner(), newOwner);
-        set_owner(H256::from(newOwner).into())
+        require(newOwner != Address::zero(), BridgeError::ZeroAddress);
+        self.OwnershipTransferStarted(self.owner(), newOwner);
+        set_pendingOwner(newOwner)
+    }
+
+    /// Completes a transfer of ownership.  The admin and validator manager
+    /// roles move from the previous owner to the new one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not the pending owner.
+    #[allow(non_snake_case)]
+    fn claimOwnership(&mut self);
+
+    /// Cancels a pending transfer of ownership.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not the owner, or if no transfer is pending.
+    #[allow(non_snake_case)]
+    fn cancelOwnershipTransfer(&mut self) {
+        self.onlyOwner();
+        let pendingOwner = self.pendingOwner();
+        require(
+            pendingOwner != Address::zero(),
+            BridgeError::NoOwnershipTransferPending,
+        );
+        self.OwnershipTransferCancelled(pendingOwner);
+        set_pendingOwner(Address::zero())
     }
 }
 struct BridgeValidatorsImpl;
 
+impl EternalStorage for BridgeValidatorsImpl {}
+
+impl AccessControlTrait for BridgeValidatorsImpl {}
+
 impl BridgeValidatorsTrait for BridgeValidatorsImpl {
+    fn constructor(&mut self) {
+        self.grant_role(DEFAULT_ADMIN_ROLE, sender());
+    }
+
     fn initialize(
         &mut self,
         required_signatures: U256,
         initial_validators: Vec<Address>,
         owner: Address,
     ) -> bool {
-        assert!(required_signatures < usize::MAX.into());
-        assert!(!self.isInitialized());
-        assert_ne!(owner, Address::zero());
-        set_owner(H256::from(owner).into());
-        assert_ne!(required_signatures, U256::zero());
-        let mut buf = [0; 32];
-        assert!(U256::from(initial_validators.len()) >= required_signatures);
+        require(
+            required_signatures < usize::MAX.into(),
+            BridgeError::InvalidRequiredSignatures,
+        );
+        self.onlyInitializer();
+        require(!self.isInitialized(), BridgeError::AlreadyInitialized);
+        require(owner != Address::zero(), BridgeError::ZeroAddress);
+        set_owner(owner);
+        self.setup_roles(owner, &[VALIDATOR_MANAGER_ROLE]);
+        require(
+            !required_signatures.is_zero()
+                && U256::from(initial_validators.len()) >= required_signatures,
+            BridgeError::InvalidRequiredSignatures,
+        );
         for &inital_validator in &initial_validators {
-            assert_ne!(inital_validator, Address::zero());
-            assert!(!self.isValidator(inital_validator));
-            (self.validatorCount() + 1u32).to_little_endian(&mut buf);
-            set_validatorCount(buf);
-            self.set_validator(inital_validator, true);
-            self.ValidatorAdded(inital_validator);
+            self.insert_validator(inital_validator);
         }
-        set_requiredSignatures(required_signatures.into());
-        set_deployedAtBlock(U256::from(block_number()).into());
-        let q = [
-            1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0,
-        ];
-        set_isInitialized(q);
+        set_requiredSignatures(required_signatures);
+        set_deployedAtBlock(block_number().into());
+        set_isInitialized(true);
         self.RequiredSignaturesChanged(required_signatures);
         return self.isInitialized();
     }
 
+    fn claimOwnership(&mut self) {
+        let new_owner = self.pendingOwner();
+        require(
+            new_owner != Address::zero(),
+            BridgeError::NoOwnershipTransferPending,
+        );
+        require(sender() == new_owner, BridgeError::NotPendingOwner);
+        self.transfer_roles(self.owner(), new_owner, &[VALIDATOR_MANAGER_ROLE]);
+        self.OwnershipTransferred(self.owner(), new_owner);
+        set_owner(new_owner);
+        set_pendingOwner(Address::zero())
+    }
+
     fn addValidator(&mut self, validator: Address) {
-        assert_eq!(sender(), self.owner());
-        assert_ne!(validator, Address::zero());
-        assert!(!self.isValidator(validator));
-        set_validatorCount((U256::from(get_validatorCount()) + U256::from(1)).into());
-        self.set_validator(validator, true);
-        self.ValidatorAdded(validator);
+        self.onlyRole(VALIDATOR_MANAGER_ROLE);
+        self.insert_validator(validator);
+    }
+
+    fn addRewardableValidator(&mut self, validator: Address, reward: Address) {
+        require(reward != Address::zero(), BridgeError::ZeroAddress);
+        self.addValidator(validator);
+        self.set_reward_address(validator, reward);
+    }
+
+    fn setValidatorRewardAddress(&mut self, validator: Address, reward: Address) {
+        let sender = sender();
+        require(
+            sender == validator || self.hasRole(VALIDATOR_MANAGER_ROLE, sender),
+            BridgeError::MissingRole,
+        );
+        require(self.isValidator(validator), BridgeError::UnknownValidator);
+        require(reward != Address::zero(), BridgeError::ZeroAddress);
+        self.set_reward_address(validator, reward);
+    }
+
+    fn getValidatorRewardAddress(&self, validator: Address) -> Address {
+        get_validatorsRewards(validator)
     }
 
     fn removeValidator(&mut self, validator: Address) {
-        assert_eq!(sender(), self.owner());
+        self.onlyRole(VALIDATOR_MANAGER_ROLE);
         let old_validator_count = self.validatorCount();
-        assert!(old_validator_count > self.requiredSignatures());
-        assert!(self.isValidator(validator));
-        self.set_validator(validator, false);
-        set_validatorCount((old_validator_count - U256::from(1)).into());
+        require(
+            old_validator_count > self.requiredSignatures(),
+            BridgeError::InvalidRequiredSignatures,
+        );
+        require(self.isValidator(validator), BridgeError::UnknownValidator);
+        set_validators(validator, false);
+        self.remove_from_list(validator);
+        set_validatorsRewards(validator, Address::zero());
+        set_validatorCount(old_validator_count - U256::from(1));
         self.ValidatorRemoved(validator);
     }
 
     fn setRequiredSignatures(&mut self, required_signatures: U256) {
-        assert_eq!(sender(), self.owner());
-        assert!(self.validatorCount() >= required_signatures);
-        assert_ne!(required_signatures, 0.into());
-        assert!(required_signatures <= usize::MAX.into());
-        set_requiredSignatures(required_signatures.into());
+        self.onlyRole(VALIDATOR_MANAGER_ROLE);
+        require(
+            self.validatorCount() >= required_signatures
+                && !required_signatures.is_zero()
+                && required_signatures <= usize::MAX.into(),
+            BridgeError::InvalidRequiredSignatures,
+        );
+        set_requiredSignatures(required_signatures);
         self.RequiredSignaturesChanged(required_signatures);
     }
 
     fn requiredSignatures(&self) -> U256 {
-        U256::from(get_requiredSignatures())
+        get_requiredSignatures()
     }
 
     fn deployedAtBlock(&self) -> U256 {
-        U256::from(get_deployedAtBlock())
+        get_deployedAtBlock()
     }
 
     fn getBridgeValidatorsInterfacesVersion(&self) -> (u64, u64, u64) {
@@ -199,91 +348,463 @@ impl BridgeValidatorsTrait for BridgeValidatorsImpl {
     }
 
     fn validatorCount(&self) -> U256 {
-        U256::from(get_validatorCount())
+        get_validatorCount()
     }
 
     fn validators(&self, validator: Address) -> bool {
-        let mut hasher = Keccak::new_sha3_256();
-        hasher.update(b"validators");
-        hasher.update(&validator[..]);
-        let mut q = [0; 32];
-        hasher.finalize(&mut q);
-        read(&q.into())
-            == [
-                1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0,
-            ]
+        get_validators(validator)
+    }
+
+    fn validatorList(&self) -> Vec<Address> {
+        let mut list = Vec::new();
+        let mut validator = self.next(list_head());
+        while validator != list_head() {
+            list.push(validator);
+            validator = self.next(validator);
+        }
+        list
+    }
+
+    fn linkValidator(&mut self, validator: Address) {
+        self.onlyRole(VALIDATOR_MANAGER_ROLE);
+        require(self.isValidator(validator), BridgeError::UnknownValidator);
+        require(!self.is_linked(validator), BridgeError::AlreadyLinked);
+        self.append_to_list(validator);
+    }
+
+    fn getNextValidator(&self, validator: Address) -> Address {
+        let validator = if validator == Address::zero() {
+            list_head()
+        } else {
+            require(self.isValidator(validator), BridgeError::UnknownValidator);
+            validator
+        };
+        let next = self.next(validator);
+        if next == list_head() {
+            Address::zero()
+        } else {
+            next
+        }
     }
 
     fn isInitialized(&self) -> bool {
-        get_isInitialized()[..] == <[u8; 32]>::from(U256::from(0))[..]
+        get_isInitialized()
     }
 
     #[allow(non_snake_case)]
     fn onlyOwner(&self) {
-        assert_eq!(sender(), self.owner())
+        require(sender() == self.owner(), BridgeError::NotOwner)
     }
 
     fn owner(&self) -> Address {
-        H256::from(get_owner()).into()
+        get_owner()
+    }
+}
+
+/// Runs `BridgeValidators` on the call data `input`, returning its output.
+#[cfg(any(feature = "bridge-validators", not(target_arch = "wasm32")))]
+pub fn run_bridge_validators(input: &[u8]) -> Vec<u8> {
+    if crate::dispatch::calls_any(input, access_control::ACCESS_CONTROL_METHODS) {
+        access_control::AccessControl::new(BridgeValidatorsImpl).dispatch(input)
+    } else {
+        BridgeValidators::new(BridgeValidatorsImpl).dispatch(input)
     }
 }
 
+/// Runs the constructor of `BridgeValidators` on `input`.
+#[cfg(any(feature = "bridge-validators", not(target_arch = "wasm32")))]
+pub fn construct_bridge_validators(input: &[u8]) {
+    BridgeValidators::new(BridgeValidatorsImpl).dispatch_ctor(input)
+}
+
+#[cfg(feature = "bridge-validators")]
 #[no_mangle]
 pub fn call() {
-    let mut endpoint = BridgeValidators::new(BridgeValidatorsImpl);
-    pwasm_ethereum::ret(&endpoint.dispatch(&pwasm_ethereum::input()));
+    crate::ext::ret(&run_bridge_validators(&crate::ext::input()));
 }
 
+#[cfg(feature = "bridge-validators")]
 #[no_mangle]
 pub fn deploy() {
-    let mut endpoint = BridgeValidators::new(BridgeValidatorsImpl);
-    endpoint.dispatch_ctor(&pwasm_ethereum::input());
+    construct_bridge_validators(&crate::ext::input());
+}
+
+/// The sentinel node of the validator list.  The list is circular and doubly
+/// linked, so that validators can be removed in constant time.
+fn list_head() -> Address {
+    Address::from([0xff; 20])
 }
 
 impl BridgeValidatorsImpl {
-    fn set_validator(&self, validator: Address, is_validator: bool) {
-        let mut hasher = Keccak::new_sha3_256();
-        hasher.update(b"validators");
-        hasher.update(&validator[..]);
-        let mut q = [0; 32];
-        hasher.finalize(&mut q);
-        write(
-            &q.into(),
-            &[
-                is_validator.into(),
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
+    /// The links of the sentinel are unset until the first validator is
+    /// added, and an unset link points back to the sentinel.
+    fn link(address: Address) -> Address {
+        if address == Address::zero() {
+            list_head()
+        } else {
+            address
+        }
+    }
+
+    fn next(&self, validator: Address) -> Address {
+        Self::link(get_validatorsNext(validator))
+    }
+
+    fn prev(&self, validator: Address) -> Address {
+        Self::link(get_validatorsPrev(validator))
+    }
+
+    /// Adds `validator` to the end of the validator list.
+    fn append_to_list(&self, validator: Address) {
+        let last = self.prev(list_head());
+        set_validatorsNext(last, validator);
+        set_validatorsPrev(validator, last);
+        set_validatorsNext(validator, list_head());
+        set_validatorsPrev(list_head(), validator);
+    }
+
+    /// Tells whether `validator` is in the validator list.  Every validator
+    /// added since the list was introduced is, and its next link is set,
+    /// even if it is the last one.  Validators added by an earlier
+    /// implementation are not, until they are linked or added again.
+    fn is_linked(&self, validator: Address) -> bool {
+        get_validatorsNext(validator) != Address::zero()
+    }
+
+    /// Unlinks `validator` from the validator list, if it is in it.
+    fn remove_from_list(&self, validator: Address) {
+        if !self.is_linked(validator) {
+            // Unlinking it would join the sentinel to itself, emptying the
+            // list.
+            return;
+        }
+        let (prev, next) = (self.prev(validator), self.next(validator));
+        set_validatorsNext(prev, next);
+        set_validatorsPrev(next, prev);
+        set_validatorsNext(validator, Address::zero());
+        set_validatorsPrev(validator, Address::zero());
+    }
+
+    /// Makes `validator` a validator, and appends it to the validator list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `validator` is the zero address or the sentinel of the
+    /// list, or is already a validator.
+    fn insert_validator(&mut self, validator: Address) {
+        require(validator != Address::zero(), BridgeError::ZeroAddress);
+        require(validator != list_head(), BridgeError::ReservedAddress);
+        require(!self.isValidator(validator), BridgeError::AlreadyValidator);
+        set_validatorCount(get_validatorCount() + U256::from(1));
+        set_validators(validator, true);
+        self.append_to_list(validator);
+        self.ValidatorAdded(validator);
+    }
+
+    fn set_reward_address(&mut self, validator: Address, reward: Address) {
+        set_validatorsRewards(validator, reward);
+        self.ValidatorRewardAddressChanged(validator, reward);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::native;
+    use crate::simulator::event_topic;
+    use std::vec;
+
+    fn owner() -> Address {
+        Address::from([0xaa; 20])
+    }
+
+    fn validator(i: u8) -> Address {
+        Address::from([i; 20])
+    }
+
+    /// Deploys the contract from `owner`.
+    fn deploy() {
+        native::reset();
+        native::with(|ext| ext.sender = owner());
+        BridgeValidatorsImpl.constructor();
+    }
+
+    /// Initializes the contract with validators `1..=count`, as `owner`.
+    fn initialize(count: u8, required_signatures: u64) {
+        deploy();
+        BridgeValidatorsImpl.initialize(
+            required_signatures.into(),
+            (1..=count).map(validator).collect(),
+            owner(),
+        );
+    }
+
+    fn reverts_with<R>(f: impl FnOnce() -> R) -> Option<BridgeError> {
+        BridgeError::decode(&native::catch_revert(f).err()?)
+    }
+
+    #[test]
+    fn rejects_list_sentinel_as_validator() {
+        initialize(1, 1);
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.addValidator(list_head())),
+            Some(BridgeError::ReservedAddress)
+        );
+        deploy();
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.initialize(1.into(), vec![list_head()], owner())),
+            Some(BridgeError::ReservedAddress)
+        );
+    }
+
+    #[test]
+    fn removes_validator_added_before_list() {
+        initialize(2, 1);
+        // What an implementation without the list leaves behind.
+        set_validators(validator(3), true);
+        set_validatorCount(3.into());
+        assert_eq!(
+            BridgeValidatorsImpl.validatorList(),
+            vec![validator(1), validator(2)]
+        );
+        BridgeValidatorsImpl.removeValidator(validator(3));
+        assert!(!BridgeValidatorsImpl.isValidator(validator(3)));
+        assert_eq!(
+            BridgeValidatorsImpl.validatorList(),
+            vec![validator(1), validator(2)]
+        );
+        BridgeValidatorsImpl.addValidator(validator(3));
+        BridgeValidatorsImpl.removeValidator(validator(2));
+        assert_eq!(
+            BridgeValidatorsImpl.validatorList(),
+            vec![validator(1), validator(3)]
+        );
+    }
+
+    #[test]
+    fn links_validator_added_before_list() {
+        initialize(2, 1);
+        // What an implementation without the list leaves behind.
+        set_validators(validator(3), true);
+        set_validatorCount(3.into());
+        native::with(|ext| ext.sender = validator(1));
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.linkValidator(validator(3))),
+            Some(BridgeError::MissingRole)
+        );
+        native::with(|ext| ext.sender = owner());
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.linkValidator(validator(4))),
+            Some(BridgeError::UnknownValidator)
+        );
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.linkValidator(validator(2))),
+            Some(BridgeError::AlreadyLinked)
+        );
+        BridgeValidatorsImpl.linkValidator(validator(3));
+        let list = BridgeValidatorsImpl.validatorList();
+        assert_eq!(list, vec![validator(1), validator(2), validator(3)]);
+        assert_eq!(
+            U256::from(list.len()),
+            BridgeValidatorsImpl.validatorCount()
+        );
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.linkValidator(validator(3))),
+            Some(BridgeError::AlreadyLinked)
+        );
+        BridgeValidatorsImpl.removeValidator(validator(2));
+        assert_eq!(
+            BridgeValidatorsImpl.validatorList(),
+            vec![validator(1), validator(3)]
+        );
+    }
+
+    #[test]
+    fn only_admin_initializes() {
+        deploy();
+        native::with(|ext| ext.sender = validator(1));
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.initialize(
+                1.into(),
+                vec![validator(1)],
+                validator(1)
+            )),
+            Some(BridgeError::MissingRole)
+        );
+        assert!(!BridgeValidatorsImpl.isInitialized());
+        native::with(|ext| ext.sender = owner());
+        BridgeValidatorsImpl.initialize(1.into(), vec![validator(1)], owner());
+        assert!(BridgeValidatorsImpl.hasRole(VALIDATOR_MANAGER_ROLE, owner()));
+    }
+
+    #[test]
+    fn claiming_ownership_moves_roles() {
+        initialize(1, 1);
+        let new_owner = validator(9);
+        BridgeValidatorsImpl.transferOwnership(new_owner);
+        native::with(|ext| ext.sender = new_owner);
+        BridgeValidatorsImpl.claimOwnership();
+        assert_eq!(BridgeValidatorsImpl.owner(), new_owner);
+        for &role in &[DEFAULT_ADMIN_ROLE, VALIDATOR_MANAGER_ROLE] {
+            assert!(BridgeValidatorsImpl.hasRole(role, new_owner));
+            assert!(!BridgeValidatorsImpl.hasRole(role, owner()));
+        }
+        BridgeValidatorsImpl.addValidator(validator(2));
+        native::with(|ext| ext.sender = owner());
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.addValidator(validator(3))),
+            Some(BridgeError::MissingRole)
+        );
+    }
+
+    #[test]
+    fn only_role_admin_sets_role_admin() {
+        initialize(1, 1);
+        let manager_admin = [0x11; 32];
+        native::with(|ext| ext.sender = validator(1));
+        assert_eq!(
+            reverts_with(
+                || BridgeValidatorsImpl.setRoleAdmin(VALIDATOR_MANAGER_ROLE, manager_admin)
+            ),
+            Some(BridgeError::MissingRole)
+        );
+        native::with(|ext| ext.sender = owner());
+        let logs = native::with(|ext| ext.logs.len());
+        BridgeValidatorsImpl.setRoleAdmin(VALIDATOR_MANAGER_ROLE, manager_admin);
+        assert_eq!(
+            BridgeValidatorsImpl.getRoleAdmin(VALIDATOR_MANAGER_ROLE),
+            manager_admin
+        );
+        let topics = native::with(|ext| ext.logs[logs..].to_vec())
+            .into_iter()
+            .map(|log| log.topics[0])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            topics,
+            vec![event_topic("RoleAdminChanged(bytes32,bytes32,bytes32)")]
+        );
+        // The admin role no longer administers the validator managers.
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.grantRole(VALIDATOR_MANAGER_ROLE, validator(1))),
+            Some(BridgeError::MissingRole)
+        );
+    }
+
+    #[test]
+    fn initializes_validators() {
+        initialize(3, 2);
+        assert!(BridgeValidatorsImpl.isInitialized());
+        assert_eq!(BridgeValidatorsImpl.owner(), owner());
+        assert_eq!(BridgeValidatorsImpl.validatorCount(), 3.into());
+        assert_eq!(BridgeValidatorsImpl.requiredSignatures(), 2.into());
+        assert_eq!(
+            BridgeValidatorsImpl.validatorList(),
+            vec![validator(1), validator(2), validator(3)]
+        );
+        assert!(BridgeValidatorsImpl.isValidator(validator(3)));
+        assert!(!BridgeValidatorsImpl.isValidator(validator(4)));
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.initialize(1.into(), vec![validator(4)], owner())),
+            Some(BridgeError::AlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_initial_validators() {
+        for &(required, ref validators, error) in &[
+            (
                 0,
-            ],
-        )
+                vec![validator(1)],
+                BridgeError::InvalidRequiredSignatures,
+            ),
+            (
+                2,
+                vec![validator(1)],
+                BridgeError::InvalidRequiredSignatures,
+            ),
+            (1, vec![Address::zero()], BridgeError::ZeroAddress),
+            (
+                1,
+                vec![validator(1), validator(1)],
+                BridgeError::AlreadyValidator,
+            ),
+        ] {
+            deploy();
+            assert_eq!(
+                reverts_with(|| BridgeValidatorsImpl.initialize(
+                    required.into(),
+                    validators.clone(),
+                    owner()
+                )),
+                Some(error)
+            );
+        }
+    }
+
+    #[test]
+    fn adds_and_removes_validators() {
+        initialize(2, 1);
+        BridgeValidatorsImpl.addValidator(validator(3));
+        assert_eq!(BridgeValidatorsImpl.validatorCount(), 3.into());
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.addValidator(validator(3))),
+            Some(BridgeError::AlreadyValidator)
+        );
+        BridgeValidatorsImpl.removeValidator(validator(1));
+        assert!(!BridgeValidatorsImpl.isValidator(validator(1)));
+        assert_eq!(BridgeValidatorsImpl.validatorCount(), 2.into());
+        assert_eq!(
+            BridgeValidatorsImpl.validatorList(),
+            vec![validator(2), validator(3)]
+        );
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.removeValidator(validator(1))),
+            Some(BridgeError::UnknownValidator)
+        );
+        let topics: Vec<_> = native::with(|ext| ext.logs.iter().map(|log| log.topics[0]).collect());
+        assert_eq!(
+            topics
+                .iter()
+                .filter(|&&topic| topic == event_topic("ValidatorRemoved(address)"))
+                .count(),
+            1
+        );
+
+        native::with(|ext| ext.sender = validator(2));
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.addValidator(validator(4))),
+            Some(BridgeError::MissingRole)
+        );
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.removeValidator(validator(3))),
+            Some(BridgeError::MissingRole)
+        );
+    }
+
+    #[test]
+    fn bounds_required_signatures() {
+        initialize(3, 3);
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.removeValidator(validator(1))),
+            Some(BridgeError::InvalidRequiredSignatures)
+        );
+        for &required in &[0, 4] {
+            assert_eq!(
+                reverts_with(|| BridgeValidatorsImpl.setRequiredSignatures(required.into())),
+                Some(BridgeError::InvalidRequiredSignatures)
+            );
+        }
+        BridgeValidatorsImpl.setRequiredSignatures(2.into());
+        assert_eq!(BridgeValidatorsImpl.requiredSignatures(), 2.into());
+        BridgeValidatorsImpl.removeValidator(validator(1));
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.removeValidator(validator(2))),
+            Some(BridgeError::InvalidRequiredSignatures)
+        );
+        native::with(|ext| ext.sender = validator(2));
+        assert_eq!(
+            reverts_with(|| BridgeValidatorsImpl.setRequiredSignatures(1.into())),
+            Some(BridgeError::MissingRole)
+        );
     }
 }
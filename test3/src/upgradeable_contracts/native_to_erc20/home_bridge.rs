@@ -0,0 +1,151 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+use super::super::access_control::AccessControlInternal;
+use super::super::basic_home_bridge::{
+    BasicHomeBridgeContract, HomeBridgeMode, HOME_BRIDGE_OWNER_ROLES,
+};
+use super::super::initializable::Initializable;
+use super::super::ownable::OwnableInternal;
+use crate::bridge_validators::ValidatorContract;
+use crate::error::{require, BridgeError};
+use crate::ext::{call, sender, value};
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::types::{Address, U256};
+
+#[eth_abi(HomeBridgeNativeToErc)]
+pub trait HomeBridgeNativeToErcInterface {
+    /// Sets the validator contract and the owner of the bridge.
+    /// The owner is also granted the admin, limits manager, and pauser
+    /// roles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is neither an admin nor an upgrader, if the
+    /// bridge has already been initialized, or if either address is zero.
+    fn initialize(&mut self, validatorContract: Address, owner: Address) -> bool;
+
+    #[constant]
+    fn isInitialized(&self) -> bool;
+
+    #[constant]
+    fn deployedAtBlock(&self) -> U256;
+}
+
+/// The gas passed along with released coins: the stipend of Solidity's
+/// `send` and `transfer`, which is enough to log an event but not to call
+/// back into the bridge.
+const TRANSFER_GAS: u64 = 2300;
+
+/// Releases locked native coins.
+pub struct NativeToErcHomeMode;
+
+impl HomeBridgeMode for NativeToErcHomeMode {
+    fn onExecuteAffirmation(&mut self, recipient: Address, value: U256) -> bool {
+        call(TRANSFER_GAS, &recipient, value, &[], &mut []).is_ok()
+    }
+}
+
+pub type HomeBridgeNativeToErcContract = BasicHomeBridgeContract<NativeToErcHomeMode>;
+
+impl HomeBridgeNativeToErcContract {
+    /// The payable fallback.  Locks the coins sent, and requests that the
+    /// same amount of tokens be minted to the sender on the foreign chain.
+    pub fn fallback(&mut self) {
+        self.requestSignature(sender(), value())
+    }
+}
+
+impl HomeBridgeNativeToErcInterface for HomeBridgeNativeToErcContract {
+    fn initialize(&mut self, validatorContract: Address, owner: Address) -> bool {
+        require(owner != Address::zero(), BridgeError::ZeroAddress);
+        self.onlyInitializer();
+        self.set_initialized();
+        ValidatorContract.set_address(validatorContract);
+        self.setOwner(owner);
+        self.setup_roles(owner, HOME_BRIDGE_OWNER_ROLES);
+        self.is_initialized()
+    }
+
+    fn isInitialized(&self) -> bool {
+        self.is_initialized()
+    }
+
+    fn deployedAtBlock(&self) -> U256 {
+        self.deployed_at_block()
+    }
+}
+
+/// Runs the bridge on the call data `input`, returning its output.
+#[cfg(any(feature = "home-bridge-native-to-erc", not(target_arch = "wasm32")))]
+pub fn run(input: &[u8]) -> pwasm_std::Vec<u8> {
+    use super::super::basic_home_bridge::dispatch;
+    use pwasm_abi::eth::EndpointInterface;
+    let mut contract = HomeBridgeNativeToErcContract::new(NativeToErcHomeMode);
+    if input.is_empty() {
+        contract.fallback();
+        pwasm_std::Vec::new()
+    } else {
+        match dispatch(contract, input) {
+            Ok(result) => result,
+            Err(contract) => HomeBridgeNativeToErc::new(contract).dispatch(input),
+        }
+    }
+}
+
+#[cfg(feature = "home-bridge-native-to-erc")]
+#[no_mangle]
+pub fn call() {
+    crate::ext::ret(&run(&crate::ext::input()));
+}
+
+#[cfg(feature = "home-bridge-native-to-erc")]
+#[no_mangle]
+pub fn deploy() {
+    // The bridge runs behind a proxy, so it is set up by `initialize`
+    // rather than by a constructor.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::native;
+
+    fn recipient() -> Address {
+        Address::from([0x77; 20])
+    }
+
+    #[test]
+    fn releases_coins_with_stipend() {
+        native::reset();
+        assert!(NativeToErcHomeMode.onExecuteAffirmation(recipient(), 5.into()));
+        native::with(|ext| {
+            assert_eq!(ext.calls.len(), 1);
+            assert_eq!(ext.calls[0].gas, TRANSFER_GAS);
+            assert_eq!(ext.calls[0].address, recipient());
+            assert_eq!(ext.calls[0].value, 5.into());
+            assert!(ext.calls[0].input.is_empty());
+        });
+    }
+
+    #[test]
+    fn fails_if_recipient_reverts() {
+        native::reset();
+        native::with(|ext| ext.stub_call(recipient(), |_| Err(Vec::new())));
+        assert!(!NativeToErcHomeMode.onExecuteAffirmation(recipient(), 5.into()));
+    }
+}
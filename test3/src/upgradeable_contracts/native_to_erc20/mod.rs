@@ -0,0 +1,22 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Bridges the native coin of the home chain to an ERC677 token on the
+//! foreign chain.  Coins sent to the home bridge are locked, and the foreign
+//! bridge mints the same amount of tokens.  Tokens sent back to the foreign
+//! bridge are burnt, and the home bridge releases the locked coins.
+pub mod foreign_bridge;
+pub mod home_bridge;
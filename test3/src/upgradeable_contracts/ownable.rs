@@ -14,13 +14,302 @@
 //
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <https://www.gnu.org/licenses/>.
-use super::super::upgradeability::eternal_storage;
-use pwasm_ethereum::sender;
+#![allow(non_snake_case)]
+use super::super::upgradeability::eternal_storage::{self, EternalStorage};
+use crate::error::{require, BridgeError};
+use crate::ext::{address, gas_left, sender};
 use pwasm_abi_derive::eth_abi;
-use pwasm_std::types::{Address};
+use pwasm_std::types::Address;
+
+/// Methods of the `Ownable` endpoint.
+pub const OWNABLE_METHODS: &[&str] = &[
+    "owner()",
+    "pendingOwner()",
+    "transferOwnership(address)",
+    "claimOwnership()",
+    "cancelOwnershipTransfer()",
+];
+
+/// The roles that control a contract along with its owner.
+pub trait OwnerRoles {
+    /// Moves the roles that go with ownership from `previous_owner` to
+    /// `new_owner`.  Called when ownership is claimed.  Contracts without
+    /// roles have nothing to move.
+    fn transfer_owner_roles(&mut self, _previous_owner: Address, _new_owner: Address) {}
+}
+
+/// The part of [`OwnableTrait`] that a contract calls to claim ownership of
+/// another contract that was handed to it, such as a bridge claiming its
+/// token.  It has no supertraits, so that a client can be generated.
+#[eth_abi(OwnershipClaim, OwnershipClaimClient)]
+pub trait IOwnershipClaim {
+    #[constant]
+    fn pendingOwner(&self) -> Address;
+
+    fn claimOwnership(&mut self);
+}
+
+/// Claims ownership of `contract` if it has been handed to this contract.
+/// A bridge is handed the contracts it owns, such as its token, with
+/// `transferOwnership` before it is initialized, and claims them then.
+pub fn claim_ownership(contract: Address) {
+    let mut client = OwnershipClaimClient::new(contract).gas(gas_left());
+    if client.pendingOwner() == address() {
+        client.claimOwnership();
+    }
+}
+
 /// # Ownable
 ///
-/// This contract has an owner address providing basic authorization control
+/// This contract has an owner address providing basic authorization control.
+/// Ownership changes hands in two steps, so that a mistyped address cannot
+/// take over the contract.
 #[eth_abi(Ownable)]
-trait OwnableTrait: eternal_storage::EternalStorage {
+pub trait OwnableTrait: EternalStorage + OwnerRoles {
+    /// Event to show ownership has been transferred
+    ///
+    /// <dl>
+    ///  <dt>previousOwner</dt><dd>representing the address of the previous owner</dd>
+    ///  <dt>newOwner</dt><dd>representing the address of the new owner</dd>
+    /// </dl>
+    #[event]
+    fn OwnershipTransferred(&mut self, previousOwner: Address, newOwner: Address);
+
+    /// Tells the address of the owner.
+    #[constant]
+    fn owner(&self) -> Address {
+        self.addressStorage(eternal_storage::key(b"owner", &[]))
+    }
+
+    /// Event to show that a transfer of ownership has been started, and
+    /// that `newOwner` may now claim ownership.
+    #[event]
+    fn OwnershipTransferStarted(&mut self, previousOwner: Address, newOwner: Address);
+
+    /// Event to show that a pending transfer of ownership was cancelled.
+    #[event]
+    fn OwnershipTransferCancelled(&mut self, pendingOwner: Address);
+
+    /// Tells the address that may claim ownership, or the zero address if no
+    /// transfer of ownership is pending.
+    #[constant]
+    fn pendingOwner(&self) -> Address {
+        self.addressStorage(eternal_storage::key(b"pendingOwner", &[]))
+    }
+
+    /// Allows the current owner to start transferring control of the
+    /// contract to `newOwner`.  Ownership only changes once `newOwner` calls
+    /// `claimOwnership`.  Replaces any transfer that is already pending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not the current owner, or if `newOwner` is
+    /// the zero address.
+    fn transferOwnership(&mut self, newOwner: Address) {
+        self.onlyOwner();
+        require(newOwner != Address::zero(), BridgeError::ZeroAddress);
+        let owner = self.owner();
+        self.OwnershipTransferStarted(owner, newOwner);
+        self.setPendingOwner(newOwner);
+    }
+
+    /// Completes a transfer of ownership.  The roles that go with ownership
+    /// move from the previous owner to the new one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no transfer is pending, or if `msg.sender` is not the
+    /// pending owner.
+    fn claimOwnership(&mut self) {
+        let newOwner = self.pendingOwner();
+        require(
+            newOwner != Address::zero(),
+            BridgeError::NoOwnershipTransferPending,
+        );
+        require(sender() == newOwner, BridgeError::NotPendingOwner);
+        let previousOwner = self.owner();
+        self.transfer_owner_roles(previousOwner, newOwner);
+        self.setOwner(newOwner);
+        self.setPendingOwner(Address::zero());
+    }
+
+    /// Cancels a pending transfer of ownership.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not the owner, or if no transfer is pending.
+    fn cancelOwnershipTransfer(&mut self) {
+        self.onlyOwner();
+        let pendingOwner = self.pendingOwner();
+        require(
+            pendingOwner != Address::zero(),
+            BridgeError::NoOwnershipTransferPending,
+        );
+        self.OwnershipTransferCancelled(pendingOwner);
+        self.setPendingOwner(Address::zero());
+    }
+}
+
+/// Internal helpers for [`OwnableTrait`] that must not be part of the ABI.
+pub trait OwnableInternal: OwnableTrait {
+    /// Throws if called by any account other than the owner.
+    fn onlyOwner(&self) {
+        require(sender() == self.owner(), BridgeError::NotOwner)
+    }
+
+    /// Sets a new owner address and fires `OwnershipTransferred`.
+    fn setOwner(&mut self, newOwner: Address) {
+        let previousOwner = self.owner();
+        self.OwnershipTransferred(previousOwner, newOwner);
+        self.setAddressStorage(eternal_storage::key(b"owner", &[]), newOwner);
+    }
+
+    fn setPendingOwner(&mut self, pendingOwner: Address) {
+        self.setAddressStorage(eternal_storage::key(b"pendingOwner", &[]), pendingOwner);
+    }
+}
+
+impl<T: OwnableTrait + ?Sized> OwnableInternal for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatch::method_id;
+    use crate::ext::native;
+    use crate::upgradeable_contracts::access_control::{
+        AccessControlInternal, AccessControlTrait, DEFAULT_ADMIN_ROLE, PAUSER_ROLE,
+    };
+    use std::vec;
+
+    /// A contract whose owner holds the admin and pauser roles.
+    struct Contract;
+
+    impl EternalStorage for Contract {}
+
+    impl AccessControlTrait for Contract {}
+
+    impl OwnableTrait for Contract {}
+
+    impl OwnerRoles for Contract {
+        fn transfer_owner_roles(&mut self, previous_owner: Address, new_owner: Address) {
+            self.transfer_roles(previous_owner, new_owner, &[PAUSER_ROLE])
+        }
+    }
+
+    fn owner() -> Address {
+        Address::from([1; 20])
+    }
+
+    fn new_owner() -> Address {
+        Address::from([2; 20])
+    }
+
+    fn as_sender(account: Address) {
+        native::with(|ext| ext.sender = account);
+    }
+
+    fn deploy() {
+        native::reset();
+        Contract.setOwner(owner());
+        Contract.setup_roles(owner(), &[PAUSER_ROLE]);
+        as_sender(owner());
+    }
+
+    fn reverts_with<R>(f: impl FnOnce() -> R) -> Option<BridgeError> {
+        BridgeError::decode(&native::catch_revert(f).err()?)
+    }
+
+    #[test]
+    fn claiming_ownership_moves_roles() {
+        deploy();
+        Contract.transferOwnership(new_owner());
+        assert_eq!(Contract.owner(), owner());
+        assert_eq!(Contract.pendingOwner(), new_owner());
+        assert!(!Contract.hasRole(DEFAULT_ADMIN_ROLE, new_owner()));
+
+        as_sender(owner());
+        assert_eq!(
+            reverts_with(|| Contract.claimOwnership()),
+            Some(BridgeError::NotPendingOwner)
+        );
+        as_sender(new_owner());
+        Contract.claimOwnership();
+        assert_eq!(Contract.owner(), new_owner());
+        assert_eq!(Contract.pendingOwner(), Address::zero());
+        for &role in &[DEFAULT_ADMIN_ROLE, PAUSER_ROLE] {
+            assert!(Contract.hasRole(role, new_owner()));
+            assert!(!Contract.hasRole(role, owner()));
+        }
+        assert_eq!(
+            reverts_with(|| Contract.claimOwnership()),
+            Some(BridgeError::NoOwnershipTransferPending)
+        );
+    }
+
+    #[test]
+    fn only_owner_transfers_ownership() {
+        deploy();
+        as_sender(new_owner());
+        assert_eq!(
+            reverts_with(|| Contract.transferOwnership(new_owner())),
+            Some(BridgeError::NotOwner)
+        );
+        as_sender(owner());
+        assert_eq!(
+            reverts_with(|| Contract.transferOwnership(Address::zero())),
+            Some(BridgeError::ZeroAddress)
+        );
+    }
+
+    #[test]
+    fn cancels_transfer_of_ownership() {
+        deploy();
+        assert_eq!(
+            reverts_with(|| Contract.cancelOwnershipTransfer()),
+            Some(BridgeError::NoOwnershipTransferPending)
+        );
+        Contract.transferOwnership(new_owner());
+        Contract.cancelOwnershipTransfer();
+        assert_eq!(Contract.pendingOwner(), Address::zero());
+        as_sender(new_owner());
+        assert_eq!(
+            reverts_with(|| Contract.claimOwnership()),
+            Some(BridgeError::NoOwnershipTransferPending)
+        );
+        assert_eq!(Contract.owner(), owner());
+        assert!(Contract.hasRole(PAUSER_ROLE, owner()));
+    }
+
+    #[test]
+    fn claims_ownership_only_when_pending() {
+        native::reset();
+        let this = Address::from([7; 20]);
+        native::with(|ext| ext.address = this);
+        let contract = Address::from([9; 20]);
+        native::with(|ext| {
+            ext.stub_call(contract, move |call| {
+                Ok(if call.input[..4] == method_id("pendingOwner()")[..] {
+                    let mut output = vec![0; 12];
+                    output.extend_from_slice(&this[..]);
+                    output
+                } else {
+                    Vec::new()
+                })
+            })
+        });
+        claim_ownership(contract);
+        let inputs: Vec<_> =
+            native::with(|ext| ext.calls.iter().map(|c| c.input.clone()).collect());
+        assert_eq!(
+            inputs,
+            vec![
+                method_id("pendingOwner()").to_vec(),
+                method_id("claimOwnership()").to_vec(),
+            ]
+        );
+
+        native::with(|ext| ext.calls.clear());
+        claim_ownership(Address::from([8; 20]));
+        assert_eq!(native::with(|ext| ext.calls.len()), 1);
+    }
 }
@@ -0,0 +1,84 @@
+// Copyright 2018 POA Networks Ltd.
+//
+// This file is part of the POA Networks bridge contracts.
+//
+// The POA Networks bridge contracts are free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public License as
+// published by the Free Software Foundation, either version 3 of the License,
+// or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <https://www.gnu.org/licenses/>.
+#![allow(non_snake_case)]
+use super::access_control::{AccessControlInternal, AccessControlTrait, PAUSER_ROLE};
+use crate::error::{require, BridgeError};
+use crate::ext::sender;
+use crate::upgradeability::eternal_storage::key;
+use pwasm_abi_derive::eth_abi;
+use pwasm_std::types::Address;
+
+/// Methods of the `Pausable` endpoint.
+pub const PAUSABLE_METHODS: &[&str] = &["paused()", "pause()", "unpause()"];
+
+/// # Pausable
+///
+/// An emergency stop.  While a bridge is paused, every method that moves
+/// value reverts, so that the bridge can be frozen during an incident
+/// without an upgrade.  View methods keep working.
+#[eth_abi(Pausable)]
+pub trait PausableTrait: AccessControlTrait {
+    /// Fired when `account` pauses the contract.
+    #[event]
+    fn Paused(&mut self, account: Address);
+
+    /// Fired when `account` unpauses the contract.
+    #[event]
+    fn Unpaused(&mut self, account: Address);
+
+    /// Tells whether the contract is paused.
+    #[constant]
+    fn paused(&self) -> bool {
+        self.boolStorage(key(b"paused", &[]))
+    }
+
+    /// Pauses the contract.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not a pauser, or if the contract is
+    /// already paused.
+    fn pause(&mut self) {
+        self.onlyRole(PAUSER_ROLE);
+        self.whenNotPaused();
+        self.setBoolStorage(key(b"paused", &[]), true);
+        self.Paused(sender());
+    }
+
+    /// Unpauses the contract.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `msg.sender` is not a pauser, or if the contract is not
+    /// paused.
+    fn unpause(&mut self) {
+        self.onlyRole(PAUSER_ROLE);
+        require(self.paused(), BridgeError::NotPaused);
+        self.setBoolStorage(key(b"paused", &[]), false);
+        self.Unpaused(sender());
+    }
+}
+
+/// Internal helpers for [`PausableTrait`] that must not be part of the ABI.
+pub trait PausableInternal: PausableTrait {
+    /// Throws if the contract is paused.
+    fn whenNotPaused(&self) {
+        require(!self.paused(), BridgeError::Paused)
+    }
+}
+
+impl<T: PausableTrait + ?Sized> PausableInternal for T {}